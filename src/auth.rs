@@ -0,0 +1,468 @@
+use std::{
+    collections::HashMap,
+    env, fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process::{Command, Output, Stdio},
+};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use bollard::auth::DockerCredentials;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use tracing::{debug, instrument};
+
+use crate::{
+    error::{bail, ensure, Error, Result},
+    reference::DEFAULT_REGISTRY,
+};
+
+/// Key used by the Docker CLI for the credentials of the default registry.
+const DEFAULT_SERVER: &str = "https://index.docker.io/v1/";
+
+/// Output of the credential helpers when they have no credentials for a server.
+const CREDENTIALS_NOT_FOUND: &str = "credentials not found in native keychain";
+
+/// The Docker CLI configuration file (`~/.docker/config.json`).
+///
+/// Unknown fields are preserved when the file is written back.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct DockerConfig {
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub(crate) auths: HashMap<String, AuthEntry>,
+    #[serde(
+        rename = "credsStore",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub(crate) creds_store: Option<String>,
+    #[serde(
+        rename = "credHelpers",
+        default,
+        skip_serializing_if = "HashMap::is_empty"
+    )]
+    pub(crate) cred_helpers: HashMap<String, String>,
+    #[serde(
+        rename = "currentContext",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub(crate) current_context: Option<String>,
+    #[serde(flatten)]
+    pub(crate) other: Map<String, Value>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct AuthEntry {
+    /// Base64 encoded `username:password`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) auth: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) identitytoken: Option<String>,
+    #[serde(flatten)]
+    pub(crate) other: Map<String, Value>,
+}
+
+/// Message exchanged with the `docker-credential-*` helpers.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct HelperCredentials {
+    #[serde(rename = "ServerURL")]
+    server_url: String,
+    username: String,
+    secret: String,
+}
+
+/// Returns the path of the Docker CLI configuration file, honoring `DOCKER_CONFIG`.
+pub(crate) fn config_path() -> Result<PathBuf> {
+    let dir = match env::var_os("DOCKER_CONFIG") {
+        Some(dir) => PathBuf::from(dir),
+        None => dirs_next::home_dir()
+            .ok_or_else(|| {
+                Error::io("Couldn't find the home directory")(io::ErrorKind::NotFound.into())
+            })?
+            .join(".docker"),
+    };
+
+    Ok(dir.join("config.json"))
+}
+
+/// Returns the credentials for a registry from the Docker CLI configuration.
+///
+/// The configuration and the credential helpers are read on a blocking thread.
+pub(crate) async fn registry_credentials(registry: &str) -> Result<Option<DockerCredentials>> {
+    let registry = registry.to_string();
+
+    tokio::task::spawn_blocking(move || DockerConfig::load(&config_path()?)?.credentials(&registry))
+        .await?
+}
+
+/// Normalize a registry or server address to the registry host (e.g.
+/// `https://index.docker.io/v1/` to `docker.io`).
+pub(crate) fn normalize_registry(server: &str) -> &str {
+    let host = server
+        .strip_prefix("https://")
+        .or_else(|| server.strip_prefix("http://"))
+        .unwrap_or(server);
+
+    let host = host.split('/').next().unwrap_or(host);
+
+    match host {
+        "index.docker.io" | "registry-1.docker.io" => DEFAULT_REGISTRY,
+        host => host,
+    }
+}
+
+/// Returns the key used to store the credentials of a registry.
+fn server_address(registry: &str) -> String {
+    match normalize_registry(registry) {
+        DEFAULT_REGISTRY => DEFAULT_SERVER.to_string(),
+        registry => registry.to_string(),
+    }
+}
+
+impl DockerConfig {
+    /// Load the configuration, returning the default one if the file doesn't exist.
+    pub(crate) fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let content = fs::read_to_string(path)
+            .map_err(Error::io(format!("Failed to read {}", path.display())))?;
+
+        serde_json::from_str(&content).map_err(Error::json(format!("Invalid {}", path.display())))
+    }
+
+    pub(crate) fn save(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .map_err(Error::io(format!("Failed to create {}", dir.display())))?;
+        }
+
+        let content = serde_json::to_string_pretty(self)?;
+
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+        let mut file = options
+            .open(path)
+            .map_err(Error::io(format!("Failed to write {}", path.display())))?;
+
+        writeln!(file, "{}", content)?;
+
+        Ok(())
+    }
+
+    /// Returns the credential helper configured for the registry, if any.
+    fn helper(&self, registry: &str) -> Option<&str> {
+        self.cred_helpers
+            .iter()
+            .find(|(server, _)| normalize_registry(server) == registry)
+            .map(|(_, helper)| helper.as_str())
+            .or(self.creds_store.as_deref())
+    }
+
+    #[instrument(skip(self))]
+    pub(crate) fn credentials(&self, registry: &str) -> Result<Option<DockerCredentials>> {
+        let registry = normalize_registry(registry);
+
+        if let Some(helper) = self.helper(registry) {
+            debug!(helper, "Using credential helper");
+
+            return helper_get(helper, &server_address(registry));
+        }
+
+        let Some((server, entry)) = self
+            .auths
+            .iter()
+            .find(|(server, _)| normalize_registry(server) == registry)
+        else {
+            return Ok(None);
+        };
+
+        let (username, password) = entry
+            .auth
+            .as_deref()
+            .filter(|auth| !auth.is_empty())
+            .map(|auth| -> Result<_> {
+                let invalid = || Error::Parse(format!("Invalid auth for {}", server));
+
+                let decoded = STANDARD.decode(auth).map_err(|_| invalid())?;
+                let decoded = String::from_utf8(decoded).map_err(|_| invalid())?;
+
+                let (username, password) = decoded.split_once(':').ok_or_else(invalid)?;
+
+                Ok((Some(username.to_string()), Some(password.to_string())))
+            })
+            .transpose()?
+            .unwrap_or_default();
+
+        Ok(Some(DockerCredentials {
+            username,
+            password,
+            serveraddress: Some(server.clone()),
+            identitytoken: entry.identitytoken.clone(),
+            ..Default::default()
+        }))
+    }
+
+    /// Store the credentials for a registry, through the credential helper if configured.
+    pub(crate) fn store(&mut self, registry: &str, username: &str, password: &str) -> Result<()> {
+        let registry = normalize_registry(registry);
+        let server = server_address(registry);
+
+        if let Some(helper) = self.helper(registry) {
+            let credentials = HelperCredentials {
+                server_url: server.clone(),
+                username: username.to_string(),
+                secret: password.to_string(),
+            };
+
+            helper_call(helper, "store", &serde_json::to_string(&credentials)?)?;
+
+            // The Docker CLI keeps an empty entry to list the registry
+            self.auths.insert(server, AuthEntry::default());
+
+            return Ok(());
+        }
+
+        let auth = STANDARD.encode(format!("{}:{}", username, password));
+
+        let entry = self.auths.entry(server).or_default();
+        entry.auth = Some(auth);
+        entry.identitytoken = None;
+
+        Ok(())
+    }
+
+    /// Remove the credentials for a registry, returns false if there were none in the helper or in
+    /// the configuration.
+    pub(crate) fn erase(&mut self, registry: &str) -> Result<bool> {
+        let registry = normalize_registry(registry);
+
+        let erased = match self.helper(registry) {
+            Some(helper) => helper_erase(helper, &server_address(registry))?,
+            None => false,
+        };
+
+        let before = self.auths.len();
+
+        self.auths
+            .retain(|server, _| normalize_registry(server) != registry);
+
+        Ok(erased || self.auths.len() != before)
+    }
+}
+
+/// Get the credentials for the server from a `docker-credential-*` helper.
+fn helper_get(helper: &str, server: &str) -> Result<Option<DockerCredentials>> {
+    let output = helper_run(helper, "get", server)?;
+
+    if !output.status.success()
+        && String::from_utf8_lossy(&output.stdout).trim() == CREDENTIALS_NOT_FOUND
+    {
+        return Ok(None);
+    }
+
+    let output = helper_stdout(helper, "get", output)?;
+
+    let credentials: HelperCredentials = serde_json::from_str(&output).map_err(Error::json(
+        format!("Invalid output from docker-credential-{}", helper),
+    ))?;
+
+    // Identity tokens are stored with a special username
+    if credentials.username == "<token>" {
+        return Ok(Some(DockerCredentials {
+            identitytoken: Some(credentials.secret),
+            serveraddress: Some(credentials.server_url),
+            ..Default::default()
+        }));
+    }
+
+    Ok(Some(DockerCredentials {
+        username: Some(credentials.username),
+        password: Some(credentials.secret),
+        serveraddress: Some(credentials.server_url),
+        ..Default::default()
+    }))
+}
+
+/// Erase the credentials for the server from a `docker-credential-*` helper, returns false if
+/// there were none.
+fn helper_erase(helper: &str, server: &str) -> Result<bool> {
+    let output = helper_run(helper, "erase", server)?;
+
+    if !output.status.success()
+        && String::from_utf8_lossy(&output.stdout).trim() == CREDENTIALS_NOT_FOUND
+    {
+        return Ok(false);
+    }
+
+    helper_stdout(helper, "erase", output)?;
+
+    Ok(true)
+}
+
+fn helper_call(helper: &str, action: &str, input: &str) -> Result<String> {
+    let output = helper_run(helper, action, input)?;
+
+    helper_stdout(helper, action, output)
+}
+
+/// Run a helper action, writing the input to its stdin.
+fn helper_run(helper: &str, action: &str, input: &str) -> Result<Output> {
+    let program = format!("docker-credential-{}", helper);
+
+    let mut child = Command::new(&program)
+        .arg(action)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(Error::io(format!("Failed to execute {}", program)))?;
+
+    child
+        .stdin
+        .take()
+        .ok_or_else(|| Error::Invalid(format!("Missing stdin for {}", program)))?
+        .write_all(input.as_bytes())?;
+
+    Ok(child.wait_with_output()?)
+}
+
+/// Returns the output of a successful helper action.
+fn helper_stdout(helper: &str, action: &str, output: Output) -> Result<String> {
+    let program = format!("docker-credential-{}", helper);
+
+    let stdout = String::from_utf8_lossy(&output.stdout).trim().to_string();
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let message = if stdout.is_empty() {
+            stderr.trim()
+        } else {
+            &stdout
+        };
+
+        bail!(Invalid, "{} {} failed: {}", program, action, message);
+    }
+
+    ensure!(
+        action != "get" || !stdout.is_empty(),
+        Invalid,
+        "Empty output from {}",
+        program
+    );
+
+    Ok(stdout)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    const CONFIG: &str = r#"{
+        "auths": {
+            "https://index.docker.io/v1/": { "auth": "dXNlcjpwYXNz" },
+            "registry.example.com": { "identitytoken": "token" }
+        },
+        "currentContext": "remote"
+    }"#;
+
+    #[test]
+    fn test_normalize_registry() {
+        assert_eq!(
+            normalize_registry("https://index.docker.io/v1/"),
+            "docker.io"
+        );
+        assert_eq!(normalize_registry("docker.io"), "docker.io");
+        assert_eq!(
+            normalize_registry("http://localhost:5000/v2/"),
+            "localhost:5000"
+        );
+        assert_eq!(normalize_registry("quay.io"), "quay.io");
+    }
+
+    #[test]
+    fn test_credentials_from_auths() {
+        let config: DockerConfig = serde_json::from_str(CONFIG).unwrap();
+
+        let expected = DockerCredentials {
+            username: Some("user".to_string()),
+            password: Some("pass".to_string()),
+            serveraddress: Some(DEFAULT_SERVER.to_string()),
+            ..Default::default()
+        };
+
+        assert_eq!(config.credentials("docker.io").unwrap(), Some(expected));
+
+        let expected = DockerCredentials {
+            serveraddress: Some("registry.example.com".to_string()),
+            identitytoken: Some("token".to_string()),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            config.credentials("registry.example.com").unwrap(),
+            Some(expected)
+        );
+
+        assert_eq!(config.credentials("quay.io").unwrap(), None);
+    }
+
+    #[test]
+    fn test_helper_selection() {
+        let config = DockerConfig {
+            creds_store: Some("desktop".to_string()),
+            cred_helpers: HashMap::from([("gcr.io".to_string(), "gcloud".to_string())]),
+            ..Default::default()
+        };
+
+        assert_eq!(config.helper("gcr.io"), Some("gcloud"));
+        assert_eq!(config.helper("docker.io"), Some("desktop"));
+    }
+
+    #[test]
+    fn test_missing_helper() {
+        let config = DockerConfig {
+            creds_store: Some("dockrs-missing".to_string()),
+            ..Default::default()
+        };
+
+        assert!(matches!(
+            config.credentials("docker.io"),
+            Err(Error::Io { .. })
+        ));
+    }
+
+    #[test]
+    fn test_store_and_erase() {
+        let path = env::temp_dir().join("dockrs-test-auth").join("config.json");
+
+        let mut config: DockerConfig = serde_json::from_str(CONFIG).unwrap();
+
+        config.store("localhost:5000", "admin", "secret").unwrap();
+        config.save(&path).unwrap();
+
+        let mut loaded = DockerConfig::load(&path).unwrap();
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+
+        assert_eq!(loaded, config);
+        assert_eq!(loaded.current_context.as_deref(), Some("remote"));
+
+        let credentials = loaded.credentials("localhost:5000").unwrap().unwrap();
+
+        assert_eq!(credentials.username.as_deref(), Some("admin"));
+        assert_eq!(credentials.password.as_deref(), Some("secret"));
+
+        assert!(loaded.erase("https://index.docker.io/v1/").unwrap());
+        assert!(!loaded.erase("docker.io").unwrap());
+        assert_eq!(loaded.credentials("docker.io").unwrap(), None);
+    }
+}
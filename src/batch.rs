@@ -0,0 +1,199 @@
+use std::{future::Future, num::NonZeroUsize};
+
+use futures::{stream, StreamExt};
+
+use crate::error::{Error, Result};
+#[cfg(feature = "mock")]
+use crate::mock::MockDocker as Docker;
+#[cfg(not(feature = "mock"))]
+use bollard::Docker;
+
+/// The default number of targets of a batch command processed at the same time.
+pub const DEFAULT_PARALLEL: NonZeroUsize = NonZeroUsize::new(10).unwrap();
+
+/// The outcome of a batch command for one of its targets.
+#[derive(Debug)]
+pub struct Outcome<T> {
+    /// The target as given by the user, e.g. a container name
+    pub target: String,
+    pub result: Result<T>,
+}
+
+/// The outcomes of a batch command (e.g. `stop`), in the order of the targets.
+#[derive(Debug)]
+pub struct Batch<T> {
+    /// What was done to the targets, e.g. `stop containers`
+    pub action: &'static str,
+    pub outcomes: Vec<Outcome<T>>,
+}
+
+impl<T> Batch<T> {
+    /// Runs the operation concurrently on each target, at most `parallel` targets at a time with
+    /// a client for each of them.
+    ///
+    /// The failure of a target doesn't stop the others.
+    pub(crate) async fn run<F, Fut>(
+        docker: &Docker,
+        action: &'static str,
+        targets: &[String],
+        parallel: NonZeroUsize,
+        operation: F,
+    ) -> Self
+    where
+        F: Fn(Docker, String) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        // The operations are only created when they start, buffered keeps the targets order
+        let outcomes = stream::iter(targets)
+            .map(|target| {
+                let operation = operation(docker.clone(), target.clone());
+
+                async move {
+                    Outcome {
+                        target: target.clone(),
+                        result: operation.await,
+                    }
+                }
+            })
+            .buffered(parallel.get())
+            .collect()
+            .await;
+
+        Self { action, outcomes }
+    }
+
+    /// The targets that failed.
+    pub fn failed(&self) -> impl Iterator<Item = &Outcome<T>> {
+        self.outcomes
+            .iter()
+            .filter(|outcome| outcome.result.is_err())
+    }
+
+    /// Fails with a [`Error::Batch`] summary if any of the targets failed.
+    pub fn summary(&self) -> Result<()> {
+        let failed = self
+            .failed()
+            .map(|outcome| outcome.target.clone())
+            .collect::<Vec<_>>();
+
+        if failed.is_empty() {
+            return Ok(());
+        }
+
+        Err(Error::Batch {
+            action: self.action.to_string(),
+            failed,
+            total: self.outcomes.len(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::docker_test;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_batch() {
+        let docker = docker_test!({
+            use crate::mock::MockDocker;
+
+            let mut mock = MockDocker::new();
+
+            mock.expect_clone().returning(MockDocker::new);
+
+            mock
+        });
+
+        let targets = ["web", "db", "cache"].map(str::to_string);
+
+        let batch = Batch::run(
+            &docker,
+            "stop containers",
+            &targets,
+            NonZeroUsize::new(2).unwrap(),
+            |_, target| async move {
+                match target.as_str() {
+                    "db" => Err(Error::NotFound {
+                        message: format!("No such container: {}", target),
+                        source: None,
+                    }),
+                    _ => Ok(target.len()),
+                }
+            },
+        )
+        .await;
+
+        let results = batch
+            .outcomes
+            .iter()
+            .map(|outcome| (outcome.target.as_str(), outcome.result.as_ref().ok()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            results,
+            [("web", Some(&3)), ("db", None), ("cache", Some(&5))]
+        );
+        assert_eq!(
+            batch.summary().unwrap_err().to_string(),
+            "Failed to stop containers db (1 of 3)"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_batch_parallel() {
+        use std::sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        };
+
+        let docker = docker_test!({
+            use crate::mock::MockDocker;
+
+            let mut mock = MockDocker::new();
+
+            mock.expect_clone().returning(MockDocker::new);
+
+            mock
+        });
+
+        let targets = (0..8).map(|i| i.to_string()).collect::<Vec<_>>();
+
+        let running = Arc::new(AtomicUsize::new(0));
+        let max = Arc::new(AtomicUsize::new(0));
+
+        let batch = Batch::run(
+            &docker,
+            "stop containers",
+            &targets,
+            NonZeroUsize::new(3).unwrap(),
+            |_, target| {
+                let running = running.clone();
+                let max = max.clone();
+
+                async move {
+                    let current = running.fetch_add(1, Ordering::SeqCst) + 1;
+                    max.fetch_max(current, Ordering::SeqCst);
+
+                    tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+
+                    running.fetch_sub(1, Ordering::SeqCst);
+
+                    Ok(target)
+                }
+            },
+        )
+        .await;
+
+        assert_eq!(max.load(Ordering::SeqCst), 3);
+
+        let results = batch
+            .outcomes
+            .into_iter()
+            .map(|outcome| outcome.result.unwrap())
+            .collect::<Vec<_>>();
+
+        assert_eq!(results, targets);
+    }
+}
@@ -1,10 +1,23 @@
-use std::{collections::HashMap, io};
+use std::{
+    collections::HashMap,
+    env, io,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    num::NonZeroUsize,
+};
 
-use crate::get_port_bindings;
+use crate::{
+    error::{ensure, Error},
+    get_exposed_ports, get_port_bindings,
+    mount::{parse_mount, parse_tmpfs, parse_volume},
+    parse_device, parse_duration, parse_extra_host, parse_filter, parse_restart_policy,
+    parse_security_opt, parse_timestamp, PullPolicy, DEFAULT_PARALLEL,
+};
 use bollard::{
-    container::{Config, CreateContainerOptions},
-    models::HostConfig,
+    container::{Config, CreateContainerOptions, LogsOptions, NetworkingConfig},
+    models::{EndpointIpamConfig, EndpointSettings, HealthConfig, HostConfig},
+    system::EventsOptions,
 };
+use chrono::{DateTime, TimeZone, Utc};
 use clap::{error::Result, Args, CommandFactory, Parser, Subcommand};
 use clap_complete::{generate, Shell};
 use tracing::instrument;
@@ -18,24 +31,78 @@ pub struct Cli {
     /// Enable debug logging
     #[arg(long, global = true)]
     pub debug: bool,
+
+    /// Daemon socket to connect to (e.g. unix:///var/run/docker.sock, tcp://host:2376 or
+    /// ssh://user@host), defaults to DOCKER_HOST
+    #[arg(long, short = 'H', global = true)]
+    pub host: Option<String>,
+
+    /// Name of the context to use to connect to the daemon, defaults to DOCKER_CONTEXT or the
+    /// current context. Like the docker CLI, DOCKER_HOST takes precedence over DOCKER_CONTEXT
+    #[arg(long, short, global = true, conflicts_with = "host")]
+    pub context: Option<String>,
+
+    /// Timeout of the requests to the daemon (e.g. "30s"), defaults to 2m
+    #[arg(long, global = true)]
+    pub timeout: Option<String>,
+
+    /// Maximum number of containers or images processed at the same time by `start`, `stop`,
+    /// `kill`, `rm` and `rmi`
+    #[arg(long, global = true, default_value_t = DEFAULT_PARALLEL)]
+    pub parallel: NonZeroUsize,
 }
 
 impl Cli {
-    pub fn generate_completion(shell: Shell) {
-        generate(shell, &mut Cli::command(), "dockers", &mut io::stdout())
+    /// Writes the completion script for the shell.
+    pub fn generate_completion(shell: Shell, out: &mut impl io::Write) {
+        generate(shell, &mut Cli::command(), "dockers", out)
     }
 }
 
 #[derive(Subcommand)]
 pub enum Command {
     /// Create and run a new container from an image
-    Run(RunArgs),
+    Run(Box<RunArgs>),
+    /// Download an image from a registry
     Pull {
-        /// The image to pull
+        /// The image to pull (e.g. `nginx:1.25`, `localhost:5000/app` or `app@sha256:...`)
         image: String,
-        /// The tag to pull
-        #[arg(short, long, default_value = "latest")]
-        tag: String,
+        /// The tag to pull, if the image has no tag or digest (default "latest")
+        #[arg(short, long, conflicts_with = "all_tags")]
+        tag: Option<String>,
+        /// Download all tagged images in the repository
+        #[arg(long, short)]
+        all_tags: bool,
+        /// Set platform if server is multi-platform capable (e.g. linux/arm64)
+        #[arg(long)]
+        platform: Option<String>,
+        /// Suppress verbose output, printing only the image reference
+        #[arg(long, short)]
+        quiet: bool,
+    },
+    /// Store the credentials of a registry in the Docker CLI configuration, without verifying them
+    Login {
+        /// The registry server (default "docker.io")
+        server: Option<String>,
+        /// Username
+        #[arg(long, short)]
+        username: String,
+        /// Password
+        #[arg(long, short, conflicts_with = "password_stdin")]
+        password: Option<String>,
+        /// Take the password from stdin
+        #[arg(long)]
+        password_stdin: bool,
+    },
+    /// Log out from a registry
+    Logout {
+        /// The registry server (default "docker.io")
+        server: Option<String>,
+    },
+    /// Manage contexts
+    Context {
+        #[command(subcommand)]
+        command: ContextCommand,
     },
     /// Show statistics about the containers
     Stats {
@@ -57,10 +124,25 @@ pub enum Command {
     },
     /// Stop one or more running containers
     Stop {
+        /// Seconds to wait before killing the container
+        #[arg(long, short)]
+        time: Option<i64>,
+        /// Signal to send to the container
+        #[arg(long, short)]
+        signal: Option<String>,
         /// The container to stop
         #[arg(required = true)]
         containers: Vec<String>,
     },
+    /// Kill one or more running containers
+    Kill {
+        /// Signal to send to the container
+        #[arg(long, short, default_value = "KILL")]
+        signal: String,
+        /// The container to kill
+        #[arg(required = true)]
+        containers: Vec<String>,
+    },
     /// List containers
     Ps {
         /// Show all containers (default shows just running)
@@ -73,17 +155,8 @@ pub enum Command {
         #[arg(long, short)]
         filter: Vec<String>,
     },
-    /// Fetch the logs of a container
-    Logs {
-        /// The container to get logs from
-        container: String,
-        /// Follow log output
-        #[arg(long, short)]
-        follow: bool,
-        /// Display total file
-        #[arg(long, short = 'n')]
-        tail: Option<usize>,
-    },
+    /// Fetch the logs of one or more containers
+    Logs(LogsArgs),
     /// Remove one or more containers
     Rm {
         /// The containers to remove
@@ -109,15 +182,184 @@ pub enum Command {
         force: bool,
     },
     /// Get real time events from the server
-    Events {
-        /// Filter output based on conditions provided
-        filter: Vec<String>,
-    },
+    Events(EventsArgs),
     /// Generate shell completion for a given shell
     Completion { shell: Shell },
 }
 
-#[derive(Debug, Args)]
+#[derive(Subcommand)]
+pub enum ContextCommand {
+    /// List contexts
+    Ls,
+    /// Set the current context
+    Use {
+        /// The context to use
+        name: String,
+    },
+    /// Print the name of the current context
+    Show,
+    /// Create a context
+    Create {
+        /// The name of the context
+        name: String,
+        /// Description of the context
+        #[arg(long)]
+        description: Option<String>,
+        /// Docker endpoint of the context (e.g. "host=tcp://host:2376,ca=ca.pem,cert=cert.pem,key=key.pem")
+        #[arg(long, default_value = "")]
+        docker: String,
+    },
+    /// Remove one or more contexts
+    Rm {
+        /// The contexts to remove
+        #[arg(required = true)]
+        names: Vec<String>,
+        /// Force the removal of a context in use
+        #[arg(long, short)]
+        force: bool,
+    },
+}
+
+#[derive(Debug, Default, Args)]
+pub struct EventsArgs {
+    /// Filter output based on conditions provided
+    #[arg(long, short)]
+    pub filter: Vec<String>,
+    /// Show events created since timestamp (e.g. "2013-01-02T13:23:37Z") or relative (e.g. "42m" for 42 minutes)
+    #[arg(long)]
+    pub since: Option<String>,
+    /// Stream events until this timestamp (e.g. "2013-01-02T13:23:37Z") or relative (e.g. "42m" for 42 minutes)
+    #[arg(long)]
+    pub until: Option<String>,
+    /// Format the output using "json" or a template (e.g. "{{.Type}} {{.Actor.Attributes.name}}")
+    #[arg(long)]
+    pub format: Option<String>,
+    /// Run a shell command for each event, the command can use the template fields
+    /// (e.g. "notify-send {{.Actor.Attributes.name}}") and the DOCKRS_EVENT_* environment variables.
+    /// The fields are already quoted for the shell, so they must not be quoted again
+    #[arg(long)]
+    pub exec: Option<String>,
+}
+
+impl TryFrom<&EventsArgs> for EventsOptions<String> {
+    type Error = crate::Error;
+
+    #[instrument]
+    fn try_from(args: &EventsArgs) -> Result<Self, Self::Error> {
+        let now = Utc::now();
+
+        let timestamp = |time: &Option<String>, ceil| -> crate::Result<Option<DateTime<Utc>>> {
+            time.as_deref()
+                .map(|time| {
+                    let timestamp = whole_seconds(parse_timestamp(time, now)?, ceil);
+
+                    Utc.timestamp_opt(timestamp, 0)
+                        .single()
+                        .ok_or_else(|| Error::Parse(format!("Invalid timestamp {}", time)))
+                })
+                .transpose()
+        };
+
+        let filters =
+            args.filter
+                .iter()
+                .try_fold(HashMap::new(), |mut acc, filter| -> crate::Result<_> {
+                    let (filter, value) = parse_filter(filter)?;
+
+                    acc.entry(filter.to_string())
+                        .or_insert_with(Vec::new)
+                        .push(value.to_string());
+
+                    Ok(acc)
+                })?;
+
+        Ok(Self {
+            since: timestamp(&args.since, false)?,
+            until: timestamp(&args.until, true)?,
+            filters,
+        })
+    }
+}
+
+#[derive(Debug, Default, Args)]
+pub struct LogsArgs {
+    /// The containers to get logs from
+    #[arg(required_unless_present = "filter")]
+    pub containers: Vec<String>,
+    /// Get the logs of the containers matching the conditions provided
+    #[arg(long)]
+    pub filter: Vec<String>,
+    /// Follow log output
+    #[arg(long, short)]
+    pub follow: bool,
+    /// Number of lines to show from the end of the logs
+    #[arg(long, short = 'n')]
+    pub tail: Option<usize>,
+    /// Show timestamps
+    #[arg(long, short)]
+    pub timestamps: bool,
+    /// Show logs since timestamp (e.g. "2013-01-02T13:23:37Z") or relative (e.g. "42m" for 42 minutes)
+    #[arg(long)]
+    pub since: Option<String>,
+    /// Show logs before a timestamp (e.g. "2013-01-02T13:23:37Z") or relative (e.g. "42m" for 42 minutes)
+    #[arg(long)]
+    pub until: Option<String>,
+    /// Only show the stdout stream
+    #[arg(long, conflicts_with = "stderr")]
+    pub stdout: bool,
+    /// Only show the stderr stream
+    #[arg(long)]
+    pub stderr: bool,
+    /// Highlight stderr lines and timestamps
+    #[arg(long)]
+    pub color: bool,
+    /// Only show JSON lines with a field value (e.g. "level=error")
+    #[arg(long)]
+    pub json_field: Vec<String>,
+    /// Only show lines matching a regex
+    #[arg(long)]
+    pub grep: Option<String>,
+    /// Render JSON lines as aligned timestamp, level and message columns
+    #[arg(long)]
+    pub pretty: bool,
+}
+
+impl TryFrom<&LogsArgs> for LogsOptions<String> {
+    type Error = crate::Error;
+
+    #[instrument]
+    fn try_from(args: &LogsArgs) -> Result<Self, Self::Error> {
+        let now = Utc::now();
+
+        let timestamp = |time: &Option<String>, ceil| -> crate::Result<i64> {
+            time.as_deref()
+                .map(|time| parse_timestamp(time, now).map(|time| whole_seconds(time, ceil)))
+                .transpose()
+                .map(Option::unwrap_or_default)
+        };
+
+        Ok(Self {
+            follow: args.follow,
+            stdout: !args.stderr,
+            stderr: !args.stdout,
+            since: timestamp(&args.since, false)?,
+            until: timestamp(&args.until, true)?,
+            timestamps: args.timestamps,
+            tail: args
+                .tail
+                .map(|tail| tail.to_string())
+                .unwrap_or_else(|| "all".to_string()),
+        })
+    }
+}
+
+/// Returns the timestamp in whole seconds, rounded up with `ceil` (e.g. for `--until`) so the
+/// requested window is kept.
+fn whole_seconds(time: DateTime<Utc>, ceil: bool) -> i64 {
+    time.timestamp() + i64::from(ceil && time.timestamp_subsec_nanos() > 0)
+}
+
+#[derive(Debug, Default, Args)]
 pub struct RunArgs {
     /// The image to create the container from
     pub image: String,
@@ -130,52 +372,342 @@ pub struct RunArgs {
     /// Bind mount a volume
     #[arg(short, long)]
     pub volume: Vec<String>,
+    /// Attach a filesystem mount to the container
+    #[arg(long)]
+    pub mount: Vec<String>,
+    /// Mount a tmpfs directory
+    #[arg(long)]
+    pub tmpfs: Vec<String>,
     /// Publish a container's port(s) to the host
     #[arg(long, short)]
     pub publish: Vec<String>,
+    /// Publish all exposed ports to random ports
+    #[arg(long, short = 'P')]
+    pub publish_all: bool,
     /// Expose a port or a range of ports
     #[arg(long)]
     pub expose: Vec<String>,
     /// Automatically remove the container when it exits
     #[arg(long)]
     pub rm: bool,
+    /// Pull image before running
+    #[arg(long, value_enum, default_value_t)]
+    pub pull: PullPolicy,
+    /// Set platform if server is multi-platform capable
+    #[arg(long)]
+    pub platform: Option<String>,
+    /// Restart policy to apply when a container exits (no, always, unless-stopped, on-failure[:max-retries])
+    #[arg(long)]
+    pub restart: Option<String>,
+    /// Command to run to check health
+    #[arg(long)]
+    pub health_cmd: Option<String>,
+    /// Time between running the check (ms|s|m|h)
+    #[arg(long)]
+    pub health_interval: Option<String>,
+    /// Consecutive failures needed to report unhealthy
+    #[arg(long)]
+    pub health_retries: Option<u32>,
+    /// Maximum time to allow one check to run (ms|s|m|h)
+    #[arg(long)]
+    pub health_timeout: Option<String>,
+    /// Start period for the container to initialize before starting health-retries countdown (ms|s|m|h)
+    #[arg(long)]
+    pub health_start_period: Option<String>,
+    /// Disable any container-specified HEALTHCHECK
+    #[arg(long, conflicts_with_all = ["health_cmd", "health_interval", "health_retries", "health_timeout", "health_start_period"])]
+    pub no_healthcheck: bool,
+    /// Add Linux capabilities
+    #[arg(long)]
+    pub cap_add: Vec<String>,
+    /// Drop Linux capabilities
+    #[arg(long)]
+    pub cap_drop: Vec<String>,
+    /// Give extended privileges to this container
+    #[arg(long)]
+    pub privileged: bool,
+    /// Mount the container's root filesystem as read only
+    #[arg(long)]
+    pub read_only: bool,
+    /// Security Options
+    #[arg(long)]
+    pub security_opt: Vec<String>,
+    /// Add a host device to the container
+    #[arg(long)]
+    pub device: Vec<String>,
+    /// Add additional groups to join
+    #[arg(long)]
+    pub group_add: Vec<String>,
+    /// User namespace to use
+    #[arg(long)]
+    pub userns: Option<String>,
+    /// Run an init inside the container that forwards signals and reaps processes
+    #[arg(long)]
+    pub init: bool,
+    /// Set custom DNS servers
+    #[arg(long)]
+    pub dns: Vec<String>,
+    /// Set custom DNS search domains
+    #[arg(long)]
+    pub dns_search: Vec<String>,
+    /// Add a custom host-to-IP mapping (host:ip)
+    #[arg(long)]
+    pub add_host: Vec<String>,
+    /// IPv4 address (e.g., 172.30.100.104)
+    #[arg(long)]
+    pub ip: Option<String>,
+    /// IPv6 address (e.g., 2001:db8::33)
+    #[arg(long)]
+    pub ip6: Option<String>,
+    /// Add network-scoped alias for the container
+    #[arg(long)]
+    pub network_alias: Vec<String>,
+    /// Container MAC address (e.g., 92:d0:c6:0a:29:33)
+    #[arg(long)]
+    pub mac_address: Option<String>,
+}
+
+/// Normalize capability names to the `CAP_` prefixed upper case form used by the daemon.
+fn normalize_capabilities(capabilities: &[String]) -> Option<Vec<String>> {
+    if capabilities.is_empty() {
+        return None;
+    }
+
+    let capabilities = capabilities
+        .iter()
+        .map(|capability| {
+            let capability = capability.to_uppercase();
+
+            if capability == "ALL" || capability.starts_with("CAP_") {
+                capability
+            } else {
+                format!("CAP_{}", capability)
+            }
+        })
+        .collect();
+
+    Some(capabilities)
+}
+
+fn is_mac_address(input: &str) -> bool {
+    let parts: Vec<&str> = input.split(':').collect();
+
+    parts.len() == 6
+        && parts
+            .iter()
+            .all(|part| part.len() == 2 && part.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+impl RunArgs {
+    /// Returns the endpoint configuration for the user defined network, if any of the endpoint
+    /// options were set.
+    fn networking_config(&self) -> crate::Result<Option<NetworkingConfig<String>>> {
+        if self.ip.is_none() && self.ip6.is_none() && self.network_alias.is_empty() {
+            return Ok(None);
+        }
+
+        let network = self
+            .network
+            .as_ref()
+            .filter(|network| !matches!(network.as_str(), "bridge" | "default" | "host" | "none"))
+            .ok_or_else(|| {
+                Error::Invalid(
+                    "IP addresses and aliases can only be used on user defined networks"
+                        .to_string(),
+                )
+            })?;
+
+        if let Some(ip) = &self.ip {
+            ensure!(
+                ip.parse::<Ipv4Addr>().is_ok(),
+                Parse,
+                "Invalid IPv4 address {}",
+                ip
+            );
+        }
+
+        if let Some(ip6) = &self.ip6 {
+            ensure!(
+                ip6.parse::<Ipv6Addr>().is_ok(),
+                Parse,
+                "Invalid IPv6 address {}",
+                ip6
+            );
+        }
+
+        let ipam_config = (self.ip.is_some() || self.ip6.is_some()).then(|| EndpointIpamConfig {
+            ipv4_address: self.ip.clone(),
+            ipv6_address: self.ip6.clone(),
+            ..Default::default()
+        });
+
+        let endpoint = EndpointSettings {
+            ipam_config,
+            aliases: (!self.network_alias.is_empty()).then(|| self.network_alias.clone()),
+            ..Default::default()
+        };
+
+        Ok(Some(NetworkingConfig {
+            endpoints_config: HashMap::from([(network.clone(), endpoint)]),
+        }))
+    }
+
+    /// Returns the health check configuration, if any of the health options were set.
+    fn healthcheck(&self) -> crate::Result<Option<HealthConfig>> {
+        if self.no_healthcheck {
+            return Ok(Some(HealthConfig {
+                test: Some(vec!["NONE".to_string()]),
+                ..Default::default()
+            }));
+        }
+
+        let nanos = |duration: &Option<String>| -> crate::Result<Option<i64>> {
+            duration
+                .as_deref()
+                .map(|input| {
+                    let duration = parse_duration(input)?;
+
+                    i64::try_from(duration.as_nanos()).map_err(|_| {
+                        crate::Error::Parse(format!("Duration {} is out of range", input))
+                    })
+                })
+                .transpose()
+        };
+
+        let health = HealthConfig {
+            test: self
+                .health_cmd
+                .as_ref()
+                .map(|cmd| vec!["CMD-SHELL".to_string(), cmd.clone()]),
+            interval: nanos(&self.health_interval)?,
+            timeout: nanos(&self.health_timeout)?,
+            retries: self.health_retries.map(i64::from),
+            start_period: nanos(&self.health_start_period)?,
+        };
+
+        if health == HealthConfig::default() {
+            return Ok(None);
+        }
+
+        Ok(Some(health))
+    }
 }
 
 impl<'a: 'b, 'b> From<&'a RunArgs> for Option<CreateContainerOptions<&'b str>> {
     #[instrument]
     fn from(args: &'a RunArgs) -> Self {
-        args.name
-            .as_deref()
-            .map(|name| CreateContainerOptions::<&str> {
-                name,
-                ..Default::default()
-            })
+        if args.name.is_none() && args.platform.is_none() {
+            return None;
+        }
+
+        Some(CreateContainerOptions::<&str> {
+            name: args.name.as_deref().unwrap_or_default(),
+            platform: args.platform.as_deref(),
+        })
     }
 }
 
-impl<'a: 'b, 'b> TryFrom<&'a RunArgs> for Config<&'b str> {
-    type Error = color_eyre::eyre::Error;
+impl TryFrom<&RunArgs> for Config<String> {
+    type Error = crate::Error;
 
     #[instrument]
-    fn try_from(args: &'a RunArgs) -> Result<Self, Self::Error> {
+    fn try_from(args: &RunArgs) -> Result<Self, Self::Error> {
         let port_bindings = Some(get_port_bindings(&args.publish)?);
 
+        let restart_policy = args
+            .restart
+            .as_deref()
+            .map(parse_restart_policy)
+            .transpose()?;
+
+        let cwd = || env::current_dir().map_err(Error::io("Failed to get current directory"));
+
+        let binds = args
+            .volume
+            .iter()
+            .map(|volume| parse_volume(volume, cwd))
+            .collect::<crate::Result<Vec<_>>>()?;
+
+        let mounts = args
+            .mount
+            .iter()
+            .map(|mount| parse_mount(mount, cwd))
+            .collect::<crate::Result<Vec<_>>>()?;
+
+        let tmpfs = args
+            .tmpfs
+            .iter()
+            .map(|tmpfs| parse_tmpfs(tmpfs))
+            .collect::<crate::Result<HashMap<_, _>>>()?;
+
+        let devices = args
+            .device
+            .iter()
+            .map(|device| parse_device(device))
+            .collect::<crate::Result<Vec<_>>>()?;
+
+        let security_opt = args
+            .security_opt
+            .iter()
+            .map(|opt| parse_security_opt(opt))
+            .collect::<crate::Result<Vec<_>>>()?;
+
+        for dns in &args.dns {
+            ensure!(
+                dns.parse::<IpAddr>().is_ok(),
+                Parse,
+                "Invalid DNS server {}",
+                dns
+            );
+        }
+
+        let extra_hosts = args
+            .add_host
+            .iter()
+            .map(|host| parse_extra_host(host))
+            .collect::<crate::Result<Vec<_>>>()?;
+
+        if let Some(mac_address) = &args.mac_address {
+            ensure!(
+                is_mac_address(mac_address),
+                Parse,
+                "Invalid MAC address {}",
+                mac_address
+            );
+        }
+
         let host_config = Some(HostConfig {
-            binds: Some(args.volume.clone()),
+            binds: Some(binds),
+            mounts: (!mounts.is_empty()).then_some(mounts),
+            tmpfs: (!tmpfs.is_empty()).then_some(tmpfs),
             network_mode: args.network.clone(),
             port_bindings,
+            restart_policy,
+            cap_add: normalize_capabilities(&args.cap_add),
+            cap_drop: normalize_capabilities(&args.cap_drop),
+            privileged: args.privileged.then_some(true),
+            readonly_rootfs: args.read_only.then_some(true),
+            security_opt: (!security_opt.is_empty()).then_some(security_opt),
+            devices: (!devices.is_empty()).then_some(devices),
+            group_add: (!args.group_add.is_empty()).then(|| args.group_add.clone()),
+            userns_mode: args.userns.clone(),
+            init: args.init.then_some(true),
+            dns: (!args.dns.is_empty()).then(|| args.dns.clone()),
+            dns_search: (!args.dns_search.is_empty()).then(|| args.dns_search.clone()),
+            extra_hosts: (!extra_hosts.is_empty()).then_some(extra_hosts),
+            publish_all_ports: args.publish_all.then_some(true),
+            auto_remove: args.rm.then_some(true),
             ..Default::default()
         });
 
-        let expose = args
-            .expose
-            .iter()
-            .map(|port| (port.as_str(), HashMap::new()))
-            .collect();
+        let expose = get_exposed_ports(&args.expose)?;
 
         Ok(Self {
-            image: Some(&args.image),
+            image: Some(args.image.clone()),
             exposed_ports: Some(expose),
+            healthcheck: args.healthcheck()?,
+            mac_address: args.mac_address.clone(),
+            networking_config: args.networking_config()?,
             host_config,
             ..Default::default()
         })
@@ -193,10 +725,7 @@ mod test {
             image: "alpine".to_string(),
             name: Some("test".to_string()),
             network: None,
-            volume: vec![],
-            publish: vec![],
-            expose: vec![],
-            rm: false,
+            ..Default::default()
         };
 
         let options = CreateContainerOptions::<&str> {
@@ -213,14 +742,11 @@ mod test {
             image: "alpine".to_string(),
             name: Some("test".to_string()),
             network: None,
-            volume: vec![],
-            publish: vec![],
-            expose: vec![],
-            rm: false,
+            ..Default::default()
         };
 
-        let config = Config::<&str> {
-            image: Some("alpine"),
+        let config = Config::<String> {
+            image: Some("alpine".to_string()),
             exposed_ports: Some(HashMap::new()),
             host_config: Some(HostConfig {
                 binds: Some(vec![]),
@@ -231,5 +757,314 @@ mod test {
         };
 
         assert_eq!(config, Config::try_from(&args).unwrap());
+
+        let args = RunArgs { rm: true, ..args };
+
+        let host_config = Config::try_from(&args).unwrap().host_config.unwrap();
+
+        assert_eq!(host_config.auto_remove, Some(true));
+    }
+
+    #[test]
+    fn test_restart_and_healthcheck_from_run_args() {
+        use bollard::models::{RestartPolicy, RestartPolicyNameEnum};
+
+        let args = RunArgs {
+            image: "alpine".to_string(),
+            restart: Some("on-failure:5".to_string()),
+            health_cmd: Some("curl -f http://localhost".to_string()),
+            health_interval: Some("30s".to_string()),
+            health_retries: Some(3),
+            ..Default::default()
+        };
+
+        let config = Config::try_from(&args).unwrap();
+
+        let restart_policy = RestartPolicy {
+            name: Some(RestartPolicyNameEnum::ON_FAILURE),
+            maximum_retry_count: Some(5),
+        };
+
+        let healthcheck = HealthConfig {
+            test: Some(vec![
+                "CMD-SHELL".to_string(),
+                "curl -f http://localhost".to_string(),
+            ]),
+            interval: Some(30_000_000_000),
+            retries: Some(3),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            Some(restart_policy),
+            config.host_config.and_then(|host| host.restart_policy)
+        );
+        assert_eq!(Some(healthcheck), config.healthcheck);
+    }
+
+    #[test]
+    fn test_no_healthcheck_from_run_args() {
+        let args = RunArgs {
+            image: "alpine".to_string(),
+            no_healthcheck: true,
+            ..Default::default()
+        };
+
+        let config = Config::try_from(&args).unwrap();
+
+        let healthcheck = HealthConfig {
+            test: Some(vec!["NONE".to_string()]),
+            ..Default::default()
+        };
+
+        assert_eq!(Some(healthcheck), config.healthcheck);
+    }
+
+    #[test]
+    fn test_healthcheck_out_of_range_from_run_args() {
+        let args = RunArgs {
+            image: "alpine".to_string(),
+            health_interval: Some("3000000h".to_string()),
+            ..Default::default()
+        };
+
+        assert!(matches!(
+            Config::try_from(&args),
+            Err(crate::Error::Parse(_))
+        ));
+    }
+
+    #[test]
+    fn test_security_options_from_run_args() {
+        use bollard::models::DeviceMapping;
+
+        let args = RunArgs {
+            image: "alpine".to_string(),
+            cap_drop: vec!["all".to_string()],
+            cap_add: vec!["net_bind_service".to_string()],
+            read_only: true,
+            security_opt: vec!["no-new-privileges".to_string()],
+            device: vec!["/dev/fuse".to_string()],
+            init: true,
+            ..Default::default()
+        };
+
+        let host_config = Config::try_from(&args).unwrap().host_config.unwrap();
+
+        let expected = HostConfig {
+            binds: Some(vec![]),
+            port_bindings: Some(HashMap::new()),
+            cap_add: Some(vec!["CAP_NET_BIND_SERVICE".to_string()]),
+            cap_drop: Some(vec!["ALL".to_string()]),
+            readonly_rootfs: Some(true),
+            security_opt: Some(vec!["no-new-privileges".to_string()]),
+            devices: Some(vec![DeviceMapping {
+                path_on_host: Some("/dev/fuse".to_string()),
+                path_in_container: Some("/dev/fuse".to_string()),
+                cgroup_permissions: Some("rwm".to_string()),
+            }]),
+            init: Some(true),
+            ..Default::default()
+        };
+
+        assert_eq!(expected, host_config);
+    }
+
+    #[test]
+    fn test_networking_from_run_args() {
+        let args = RunArgs {
+            image: "alpine".to_string(),
+            network: Some("integration".to_string()),
+            network_alias: vec!["db".to_string()],
+            ip: Some("172.30.100.104".to_string()),
+            dns: vec!["1.1.1.1".to_string()],
+            add_host: vec!["registry=10.0.0.5".to_string()],
+            mac_address: Some("92:d0:c6:0a:29:33".to_string()),
+            ..Default::default()
+        };
+
+        let config = Config::try_from(&args).unwrap();
+
+        let endpoint = EndpointSettings {
+            ipam_config: Some(EndpointIpamConfig {
+                ipv4_address: Some("172.30.100.104".to_string()),
+                ..Default::default()
+            }),
+            aliases: Some(vec!["db".to_string()]),
+            ..Default::default()
+        };
+
+        let networking_config = NetworkingConfig {
+            endpoints_config: HashMap::from([("integration".to_string(), endpoint)]),
+        };
+
+        assert_eq!(Some(networking_config), config.networking_config);
+        assert_eq!(Some("92:d0:c6:0a:29:33".to_string()), config.mac_address);
+
+        let host_config = config.host_config.unwrap();
+
+        assert_eq!(Some(vec!["1.1.1.1".to_string()]), host_config.dns);
+        assert_eq!(
+            Some(vec!["registry:10.0.0.5".to_string()]),
+            host_config.extra_hosts
+        );
+    }
+
+    #[test]
+    fn test_invalid_networking_from_run_args() {
+        let cases = [
+            RunArgs {
+                network_alias: vec!["db".to_string()],
+                ..Default::default()
+            },
+            RunArgs {
+                network: Some("bridge".to_string()),
+                ip: Some("172.17.0.5".to_string()),
+                ..Default::default()
+            },
+            RunArgs {
+                network: Some("integration".to_string()),
+                ip6: Some("172.17.0.5".to_string()),
+                ..Default::default()
+            },
+            RunArgs {
+                mac_address: Some("92:d0:c6".to_string()),
+                ..Default::default()
+            },
+            RunArgs {
+                dns: vec!["dns.google".to_string()],
+                ..Default::default()
+            },
+        ];
+
+        for args in cases {
+            assert!(Config::try_from(&args).is_err(), "{:?}", args);
+        }
+    }
+
+    #[test]
+    fn test_ports_from_run_args() {
+        let args = RunArgs {
+            image: "alpine".to_string(),
+            publish: vec!["8000-8001:80-81".to_string()],
+            expose: vec!["53/udp".to_string()],
+            ..Default::default()
+        };
+
+        let config = Config::try_from(&args).unwrap();
+
+        let exposed_ports = HashMap::from([("53/udp".to_string(), HashMap::new())]);
+
+        assert_eq!(Some(exposed_ports), config.exposed_ports);
+
+        let mut ports: Vec<String> = config
+            .host_config
+            .and_then(|host| host.port_bindings)
+            .unwrap()
+            .into_keys()
+            .collect();
+        ports.sort();
+
+        assert_eq!(vec!["80/tcp", "81/tcp"], ports);
+    }
+
+    #[test]
+    fn test_logs_options_from_logs_args() {
+        let args = LogsArgs {
+            containers: vec!["test".to_string()],
+            tail: Some(10),
+            timestamps: true,
+            since: Some("2023-05-01T10:00:00Z".to_string()),
+            ..Default::default()
+        };
+
+        let options = LogsOptions::<String> {
+            stdout: true,
+            stderr: true,
+            since: 1682935200,
+            timestamps: true,
+            tail: "10".to_string(),
+            ..Default::default()
+        };
+
+        assert_eq!(options, LogsOptions::try_from(&args).unwrap());
+
+        let args = LogsArgs {
+            stderr: true,
+            ..Default::default()
+        };
+
+        let options = LogsOptions::try_from(&args).unwrap();
+
+        assert!(!options.stdout);
+        assert!(options.stderr);
+
+        let args = LogsArgs {
+            since: Some("1682935200.5".to_string()),
+            until: Some("1682935300.5".to_string()),
+            ..Default::default()
+        };
+
+        let options = LogsOptions::try_from(&args).unwrap();
+
+        // The window is rounded outwards
+        assert_eq!(options.since, 1682935200);
+        assert_eq!(options.until, 1682935301);
+
+        let args = LogsArgs {
+            until: Some("last tuesday".to_string()),
+            ..Default::default()
+        };
+
+        assert!(LogsOptions::try_from(&args).is_err());
+    }
+
+    #[test]
+    fn test_parse_events() {
+        let cli = Cli::try_parse_from([
+            "dockrs",
+            "events",
+            "--filter",
+            "event=die",
+            "--exec",
+            "echo {{.Actor.Attributes.name}}",
+        ])
+        .unwrap();
+
+        let Command::Events(args) = cli.subcommand else {
+            panic!("expected the events command");
+        };
+
+        assert_eq!(args.filter, vec!["event=die"]);
+        assert_eq!(
+            args.exec.as_deref(),
+            Some("echo {{.Actor.Attributes.name}}")
+        );
+    }
+
+    #[test]
+    fn test_events_options_from_events_args() {
+        let args = EventsArgs {
+            filter: vec!["type=container".to_string(), "event=die".to_string()],
+            since: Some("2023-05-01T10:00:00Z".to_string()),
+            ..Default::default()
+        };
+
+        let options = EventsOptions::try_from(&args).unwrap();
+
+        assert_eq!(
+            options.since,
+            Some(Utc.timestamp_opt(1682935200, 0).unwrap())
+        );
+        assert_eq!(options.until, None);
+        assert_eq!(options.filters["type"], vec!["container"]);
+        assert_eq!(options.filters["event"], vec!["die"]);
+
+        let args = EventsArgs {
+            until: Some("yesterday".to_string()),
+            ..Default::default()
+        };
+
+        assert!(EventsOptions::try_from(&args).is_err());
     }
 }
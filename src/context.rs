@@ -0,0 +1,523 @@
+use std::{
+    collections::HashMap,
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+use tracing::debug;
+
+use crate::{
+    auth::{self, DockerConfig},
+    error::{bail, ensure, Error, Result},
+    host::Host,
+};
+
+/// Name of the implicit context, using `DOCKER_HOST` or the local daemon.
+pub(crate) const DEFAULT_CONTEXT: &str = "default";
+/// Name of the Docker endpoint in the contexts.
+const DOCKER_ENDPOINT: &str = "docker";
+const DEFAULT_SOCKET: &str = "unix:///var/run/docker.sock";
+
+/// The metadata of a Docker CLI context (`contexts/meta/<id>/meta.json`).
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub(crate) struct ContextMeta {
+    pub(crate) name: String,
+    #[serde(default)]
+    pub(crate) metadata: ContextMetadata,
+    #[serde(default)]
+    pub(crate) endpoints: HashMap<String, Endpoint>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub(crate) struct ContextMetadata {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) description: Option<String>,
+    #[serde(flatten)]
+    pub(crate) other: Map<String, Value>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub(crate) struct Endpoint {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) host: Option<String>,
+    #[serde(rename = "SkipTLSVerify", default)]
+    pub(crate) skip_tls_verify: bool,
+    #[serde(flatten)]
+    pub(crate) other: Map<String, Value>,
+}
+
+/// The Docker endpoint of `context create --docker`, with the TLS material to copy in the
+/// context (e.g. `host=tcp://host:2376,ca=ca.pem,cert=cert.pem,key=key.pem`).
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub(crate) struct EndpointOptions {
+    host: String,
+    ca: Option<PathBuf>,
+    cert: Option<PathBuf>,
+    key: Option<PathBuf>,
+}
+
+impl EndpointOptions {
+    pub(crate) fn parse(input: &str) -> Result<Self> {
+        let mut options = Self::default();
+
+        for option in input.split(',').filter(|option| !option.is_empty()) {
+            let (key, value) = option
+                .split_once('=')
+                .ok_or_else(|| Error::Parse(format!("Invalid endpoint option {}", option)))?;
+
+            match key {
+                "host" => options.host = value.to_string(),
+                "ca" => options.ca = Some(PathBuf::from(value)),
+                "cert" => options.cert = Some(PathBuf::from(value)),
+                "key" => options.key = Some(PathBuf::from(value)),
+                "skip-tls-verify" => {
+                    let skip: bool = value.parse().map_err(|_| {
+                        Error::Parse(format!("Invalid value for skip-tls-verify {}", value))
+                    })?;
+
+                    ensure!(
+                        !skip,
+                        Invalid,
+                        "skip-tls-verify is not supported by this version of dockrs"
+                    );
+                }
+                _ => bail!(Parse, "Unrecognized endpoint option {}", key),
+            }
+        }
+
+        if options.host.is_empty() {
+            options.host = DEFAULT_SOCKET.to_string();
+        }
+
+        ensure!(
+            options.cert.is_some() == options.key.is_some(),
+            Parse,
+            "Invalid endpoint {}: cert and key must be given together",
+            input
+        );
+
+        Host::parse(&options.host, None)?;
+
+        Ok(options)
+    }
+}
+
+/// The contexts stored next to the Docker CLI configuration file.
+///
+/// Each context is stored in a directory named after the SHA256 of its name, in `meta/` for
+/// the metadata and `tls/` for the TLS material.
+pub(crate) struct ContextStore {
+    dir: PathBuf,
+}
+
+impl ContextStore {
+    pub(crate) fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    pub(crate) fn open() -> Result<Self> {
+        let path = auth::config_path()?;
+        let dir = path
+            .parent()
+            .ok_or_else(|| Error::Invalid("Invalid configuration path".to_string()))?
+            .join("contexts");
+
+        Ok(Self::new(dir))
+    }
+
+    fn id(name: &str) -> String {
+        format!("{:x}", Sha256::digest(name))
+    }
+
+    fn meta_dir(&self, name: &str) -> PathBuf {
+        self.dir.join("meta").join(Self::id(name))
+    }
+
+    fn tls_dir(&self, name: &str) -> PathBuf {
+        self.dir.join("tls").join(Self::id(name))
+    }
+
+    /// The contexts, sorted by name.
+    pub(crate) fn list(&self) -> Result<Vec<ContextMeta>> {
+        let dir = self.dir.join("meta");
+
+        if !dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut contexts = Vec::new();
+
+        for entry in
+            fs::read_dir(&dir).map_err(Error::io(format!("Failed to read {}", dir.display())))?
+        {
+            let path = entry?.path().join("meta.json");
+
+            if path.exists() {
+                contexts.push(read_meta(&path)?);
+            }
+        }
+
+        contexts.sort_by(|a, b| a.name.cmp(&b.name));
+
+        Ok(contexts)
+    }
+
+    pub(crate) fn load(&self, name: &str) -> Result<ContextMeta> {
+        let path = self.meta_dir(name).join("meta.json");
+
+        ensure!(
+            path.exists(),
+            NotFound,
+            "Context \"{}\" does not exist",
+            name
+        );
+
+        read_meta(&path)
+    }
+
+    pub(crate) fn exists(&self, name: &str) -> bool {
+        self.meta_dir(name).join("meta.json").exists()
+    }
+
+    pub(crate) fn create(
+        &self,
+        name: &str,
+        description: Option<&str>,
+        endpoint: &EndpointOptions,
+    ) -> Result<()> {
+        ensure!(
+            is_context_name(name),
+            Parse,
+            "Invalid context name {}: names must start with a letter or a digit and only \
+             contain letters, digits, '_', '.', '+' and '-'",
+            name
+        );
+        ensure!(
+            name != DEFAULT_CONTEXT && !self.exists(name),
+            Conflict,
+            "Context \"{}\" already exists",
+            name
+        );
+
+        let tls = [
+            ("ca.pem", &endpoint.ca),
+            ("cert.pem", &endpoint.cert),
+            ("key.pem", &endpoint.key),
+        ];
+
+        let tls_dir = self.tls_dir(name).join(DOCKER_ENDPOINT);
+
+        for (file, source) in tls {
+            let Some(source) = source else {
+                continue;
+            };
+
+            fs::create_dir_all(&tls_dir)
+                .map_err(Error::io(format!("Failed to create {}", tls_dir.display())))?;
+            fs::copy(source, tls_dir.join(file))
+                .map_err(Error::io(format!("Failed to copy {}", source.display())))?;
+        }
+
+        let meta = ContextMeta {
+            name: name.to_string(),
+            metadata: ContextMetadata {
+                description: description.map(str::to_string),
+                ..Default::default()
+            },
+            endpoints: HashMap::from([(
+                DOCKER_ENDPOINT.to_string(),
+                Endpoint {
+                    host: Some(endpoint.host.clone()),
+                    ..Default::default()
+                },
+            )]),
+        };
+
+        let dir = self.meta_dir(name);
+
+        fs::create_dir_all(&dir)
+            .map_err(Error::io(format!("Failed to create {}", dir.display())))?;
+        fs::write(dir.join("meta.json"), serde_json::to_string(&meta)?)
+            .map_err(Error::io(format!("Failed to write the context {}", name)))?;
+
+        Ok(())
+    }
+
+    pub(crate) fn remove(&self, name: &str) -> Result<()> {
+        ensure!(
+            self.exists(name),
+            NotFound,
+            "Context \"{}\" does not exist",
+            name
+        );
+
+        for dir in [self.meta_dir(name), self.tls_dir(name)] {
+            if dir.exists() {
+                fs::remove_dir_all(&dir)
+                    .map_err(Error::io(format!("Failed to remove {}", dir.display())))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The Docker endpoint of a context, `None` for the default context.
+    ///
+    /// TCP endpoints use TLS if the context has TLS material.
+    pub(crate) fn host(&self, name: &str) -> Result<Option<Host>> {
+        if name == DEFAULT_CONTEXT {
+            return Ok(None);
+        }
+
+        let meta = self.load(name)?;
+
+        let endpoint = meta.endpoints.get(DOCKER_ENDPOINT).ok_or_else(|| {
+            Error::Invalid(format!("Context \"{}\" has no docker endpoint", name))
+        })?;
+
+        ensure!(
+            !endpoint.skip_tls_verify,
+            Invalid,
+            "Context \"{}\" skips the TLS verification, which is not supported by this \
+             version of dockrs",
+            name
+        );
+
+        let host = endpoint.host.as_deref().unwrap_or(DEFAULT_SOCKET);
+
+        let tls_dir = self.tls_dir(name).join(DOCKER_ENDPOINT);
+        let tls_dir = tls_dir.exists().then_some(tls_dir.as_path());
+
+        debug!(name, host, ?tls_dir, "Using context");
+
+        Host::parse(host, tls_dir).map(Some)
+    }
+}
+
+fn read_meta(path: &Path) -> Result<ContextMeta> {
+    let content = fs::read_to_string(path)
+        .map_err(Error::io(format!("Failed to read {}", path.display())))?;
+
+    serde_json::from_str(&content).map_err(Error::json(format!("Invalid {}", path.display())))
+}
+
+fn is_context_name(name: &str) -> bool {
+    let mut chars = name.chars();
+
+    chars.next().is_some_and(|c| c.is_ascii_alphanumeric())
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '+' | '-'))
+}
+
+/// The context from `DOCKER_CONTEXT`, or the current context of the configuration.
+pub(crate) fn current_context(config: &DockerConfig) -> String {
+    env::var("DOCKER_CONTEXT")
+        .ok()
+        .filter(|context| !context.is_empty())
+        .or_else(|| config.current_context.clone())
+        .unwrap_or_else(|| DEFAULT_CONTEXT.to_string())
+}
+
+/// A context as shown by `context ls`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContextRow {
+    pub name: String,
+    pub description: String,
+    /// The host of the Docker endpoint
+    pub endpoint: String,
+    /// Whether this is the current context
+    pub current: bool,
+}
+
+/// List the contexts, starting with the default one.
+pub fn context_ls() -> Result<Vec<ContextRow>> {
+    let config = DockerConfig::load(&auth::config_path()?)?;
+    let current = current_context(&config);
+    let store = ContextStore::open()?;
+
+    let default_host = env::var("DOCKER_HOST")
+        .ok()
+        .filter(|host| !host.is_empty())
+        .unwrap_or_else(|| DEFAULT_SOCKET.to_string());
+
+    let mut rows = vec![ContextRow {
+        name: DEFAULT_CONTEXT.to_string(),
+        description: "Current DOCKER_HOST based configuration".to_string(),
+        endpoint: default_host,
+        current: current == DEFAULT_CONTEXT,
+    }];
+
+    for context in store.list()? {
+        let endpoint = context
+            .endpoints
+            .get(DOCKER_ENDPOINT)
+            .and_then(|endpoint| endpoint.host.clone())
+            .unwrap_or_default();
+
+        rows.push(ContextRow {
+            current: context.name == current,
+            name: context.name,
+            description: context.metadata.description.unwrap_or_default(),
+            endpoint,
+        });
+    }
+
+    Ok(rows)
+}
+
+/// Set the current context in the configuration.
+pub fn context_use(name: &str) -> Result<()> {
+    let path = auth::config_path()?;
+    let mut config = DockerConfig::load(&path)?;
+
+    if name != DEFAULT_CONTEXT {
+        ContextStore::open()?.load(name)?;
+    }
+
+    config.current_context = (name != DEFAULT_CONTEXT).then(|| name.to_string());
+    config.save(&path)
+}
+
+/// The name of the current context.
+pub fn context_show() -> Result<String> {
+    let config = DockerConfig::load(&auth::config_path()?)?;
+
+    Ok(current_context(&config))
+}
+
+/// Create a context from the `--docker` endpoint options.
+pub fn context_create(name: &str, description: Option<&str>, docker: &str) -> Result<()> {
+    let endpoint = EndpointOptions::parse(docker)?;
+
+    ContextStore::open()?.create(name, description, &endpoint)
+}
+
+/// Remove contexts, the current context can only be removed with `force`.
+pub fn context_rm(names: &[String], force: bool) -> Result<()> {
+    let path = auth::config_path()?;
+    let mut config = DockerConfig::load(&path)?;
+    let store = ContextStore::open()?;
+
+    for name in names {
+        ensure!(
+            name != DEFAULT_CONTEXT,
+            Invalid,
+            "Context \"{}\" cannot be removed",
+            name
+        );
+
+        let current = config.current_context.as_deref() == Some(name);
+
+        ensure!(
+            !current || force,
+            Conflict,
+            "Context \"{}\" is in use, set a different context or use --force",
+            name
+        );
+
+        // The configuration keeps pointing to the context if it can't be removed
+        store.remove(name)?;
+
+        if current {
+            config.current_context = None;
+            config.save(&path)?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_endpoint_options() {
+        let options =
+            EndpointOptions::parse("host=tcp://build:2376,ca=ca.pem,skip-tls-verify=false")
+                .unwrap();
+
+        assert_eq!(
+            options,
+            EndpointOptions {
+                host: "tcp://build:2376".to_string(),
+                ca: Some(PathBuf::from("ca.pem")),
+                ..Default::default()
+            }
+        );
+
+        assert!(matches!(
+            EndpointOptions::parse("host=tcp://build:2376,skip-tls-verify=true"),
+            Err(Error::Invalid(_))
+        ));
+
+        assert_eq!(EndpointOptions::parse("").unwrap().host, DEFAULT_SOCKET);
+        assert!(EndpointOptions::parse("host=build:2376").is_err());
+        assert!(EndpointOptions::parse("port=2376").is_err());
+        assert!(EndpointOptions::parse("host=tcp://build:2376,cert=cert.pem").is_err());
+    }
+
+    #[test]
+    fn test_context_store() {
+        let dir = env::temp_dir().join("dockrs-test-contexts");
+        let store = ContextStore::new(&dir);
+
+        let cert = dir.join("cert.pem");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(&cert, "cert").unwrap();
+
+        let endpoint = EndpointOptions {
+            host: "tcp://build:2376".to_string(),
+            cert: Some(cert),
+            ..Default::default()
+        };
+
+        store
+            .create("build", Some("Build host"), &endpoint)
+            .unwrap();
+
+        let contexts = store.list().unwrap();
+        let host = store.host("build").unwrap();
+        let exists_twice = store.create("build", None, &endpoint).is_err();
+
+        store.remove("build").unwrap();
+
+        let removed = !store.exists("build");
+
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(contexts.len(), 1);
+        assert_eq!(contexts[0].name, "build");
+        assert_eq!(
+            contexts[0].metadata.description.as_deref(),
+            Some("Build host")
+        );
+        assert_eq!(
+            host,
+            Some(Host::Tls {
+                addr: "build:2376".to_string(),
+                cert_path: dir
+                    .join("tls")
+                    .join(ContextStore::id("build"))
+                    .join("docker"),
+            })
+        );
+        assert!(exists_twice);
+        assert!(removed);
+        assert_eq!(store.host(DEFAULT_CONTEXT).unwrap(), None);
+    }
+
+    #[test]
+    fn test_context_id() {
+        assert_eq!(
+            ContextStore::id("default"),
+            "37a8eec1ce19687d132fe29051dca629d164e2c4958ba141d5f4133a33f0688f"
+        );
+    }
+}
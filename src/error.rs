@@ -0,0 +1,174 @@
+use std::io;
+
+use thiserror::Error;
+use tokio::task::JoinError;
+
+use crate::host::ConnectionError;
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// The errors returned by the library.
+#[derive(Debug, Error)]
+pub enum Error {
+    /// An invalid argument, like a port binding, a filter or an image reference
+    #[error("{0}")]
+    Parse(String),
+    /// A container, image or context that doesn't exist
+    #[error("{message}")]
+    NotFound {
+        message: String,
+        /// The error of the daemon, if it reported it
+        #[source]
+        source: Option<bollard::errors::Error>,
+    },
+    /// The request conflicts with the current state, like removing a running container
+    #[error("{message}")]
+    Conflict {
+        message: String,
+        /// The error of the daemon, if it reported it
+        #[source]
+        source: Option<bollard::errors::Error>,
+    },
+    /// The daemon couldn't be reached
+    #[error(transparent)]
+    Connection(#[from] ConnectionError),
+    /// Some of the targets of a batch command (e.g. `stop`) failed, only returned by
+    /// [`Batch::summary`](crate::Batch::summary) as the commands return the outcome of each target
+    #[error("Failed to {action} {} ({} of {total})", .failed.join(", "), .failed.len())]
+    Batch {
+        /// The action that failed, e.g. `stop containers`
+        action: String,
+        failed: Vec<String>,
+        total: usize,
+    },
+    /// An operation that can't be done, like attaching to several containers
+    #[error("{0}")]
+    Invalid(String),
+    /// Any other error of the daemon
+    #[error(transparent)]
+    Docker(bollard::errors::Error),
+    #[error("{message}")]
+    Io {
+        message: String,
+        #[source]
+        source: io::Error,
+    },
+    #[error("{message}")]
+    Json {
+        message: String,
+        #[source]
+        source: serde_json::Error,
+    },
+    #[error("Failed to join a task")]
+    Join(#[from] JoinError),
+}
+
+/// Returns early with an error of the variant, with a formatted message.
+macro_rules! bail {
+    (NotFound, $($arg:tt)+) => {
+        return Err($crate::error::Error::NotFound {
+            message: format!($($arg)+),
+            source: None,
+        })
+    };
+    (Conflict, $($arg:tt)+) => {
+        return Err($crate::error::Error::Conflict {
+            message: format!($($arg)+),
+            source: None,
+        })
+    };
+    ($variant:ident, $($arg:tt)+) => {
+        return Err($crate::error::Error::$variant(format!($($arg)+)))
+    };
+}
+
+/// Returns early with an error of the variant if the condition is false.
+macro_rules! ensure {
+    ($cond:expr, $variant:ident, $($arg:tt)+) => {
+        if !$cond {
+            $crate::error::bail!($variant, $($arg)+);
+        }
+    };
+}
+
+pub(crate) use {bail, ensure};
+
+impl Error {
+    /// Wraps an I/O error with what was being done.
+    pub(crate) fn io(message: impl Into<String>) -> impl FnOnce(io::Error) -> Self {
+        let message = message.into();
+
+        |source| Self::Io { message, source }
+    }
+
+    /// Wraps a JSON error with what was being (de)serialized.
+    pub(crate) fn json(message: impl Into<String>) -> impl FnOnce(serde_json::Error) -> Self {
+        let message = message.into();
+
+        |source| Self::Json { message, source }
+    }
+}
+
+impl From<bollard::errors::Error> for Error {
+    fn from(error: bollard::errors::Error) -> Self {
+        match &error {
+            bollard::errors::Error::DockerResponseServerError {
+                status_code: 404,
+                message,
+            } => Self::NotFound {
+                message: message.clone(),
+                source: Some(error),
+            },
+            bollard::errors::Error::DockerResponseServerError {
+                status_code: 409,
+                message,
+            } => Self::Conflict {
+                message: message.clone(),
+                source: Some(error),
+            },
+            _ => Self::Docker(error),
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(source: io::Error) -> Self {
+        Self::Io {
+            message: "I/O error".to_string(),
+            source,
+        }
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(source: serde_json::Error) -> Self {
+        Self::Json {
+            message: "Invalid JSON".to_string(),
+            source,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_from_bollard() {
+        let error = |status_code| bollard::errors::Error::DockerResponseServerError {
+            status_code,
+            message: "No such container: web".to_string(),
+        };
+
+        let not_found = Error::from(error(404));
+
+        assert!(matches!(not_found, Error::NotFound { .. }));
+        assert_eq!(not_found.to_string(), "No such container: web");
+        assert_eq!(
+            std::error::Error::source(&not_found).unwrap().to_string(),
+            "Docker responded with status code 404: No such container: web"
+        );
+        assert!(matches!(Error::from(error(409)), Error::Conflict { .. }));
+        assert!(matches!(Error::from(error(500)), Error::Docker(_)));
+    }
+}
@@ -0,0 +1,157 @@
+use bollard::{models::EventMessage, system::EventsOptions};
+use futures::{Stream, StreamExt};
+use tokio::process::Command;
+use tracing::{debug, error, instrument, warn};
+
+#[cfg(feature = "mock")]
+use crate::mock::{DockerTrait, MockDocker as Docker};
+use crate::{
+    error::{Error, Result},
+    template,
+};
+#[cfg(not(feature = "mock"))]
+use bollard::Docker;
+
+/// Prefix of the environment variables set for the `exec` commands.
+const ENV_PREFIX: &str = "DOCKRS_EVENT_";
+
+/// Streams the events of the server.
+#[instrument(skip(docker))]
+pub fn events(
+    docker: &Docker,
+    options: EventsOptions<String>,
+) -> impl Stream<Item = Result<EventMessage>> + '_ {
+    docker
+        .events(Some(options))
+        .map(|event| event.map_err(Into::into))
+}
+
+/// Runs a shell command for an event with `sh -c`, without waiting for it to exit.
+///
+/// The command is a template, and the event is also available in the `DOCKRS_EVENT_*`
+/// environment variables.
+pub fn spawn_event_command(command: &str, event: &EventMessage) -> Result<()> {
+    let command = template::render_shell(command, &serde_json::to_value(event)?)?;
+
+    debug!(command, "Running event command");
+
+    let mut child = Command::new("sh")
+        .arg("-c")
+        .arg(&command)
+        .envs(event_env(event))
+        .spawn()
+        .map_err(Error::io(format!("Failed to run {}", command)))?;
+
+    tokio::spawn(async move {
+        match child.wait().await {
+            Ok(status) if !status.success() => warn!(command, %status, "Event command failed"),
+            Ok(_) => {}
+            Err(e) => error!(?e, command, "Failed to wait for the event command"),
+        }
+    });
+
+    Ok(())
+}
+
+/// The environment variables describing an event, the actor attributes being in
+/// `DOCKRS_EVENT_ATTR_<NAME>` variables.
+fn event_env(event: &EventMessage) -> Vec<(String, String)> {
+    let mut env = vec![
+        ("TYPE", event.typ.map(|typ| typ.to_string())),
+        ("ACTION", event.action.clone()),
+        ("SCOPE", event.scope.map(|scope| scope.to_string())),
+        ("TIME", event.time.map(|time| time.to_string())),
+        ("TIME_NANO", event.time_nano.map(|time| time.to_string())),
+        (
+            "ID",
+            event.actor.as_ref().and_then(|actor| actor.id.clone()),
+        ),
+    ]
+    .into_iter()
+    .filter_map(|(name, value)| Some((format!("{}{}", ENV_PREFIX, name), value?)))
+    .collect::<Vec<_>>();
+
+    let attributes = event
+        .actor
+        .as_ref()
+        .and_then(|actor| actor.attributes.as_ref())
+        .into_iter()
+        .flatten();
+
+    for (name, value) in attributes {
+        let name = name
+            .chars()
+            .map(|c| match c {
+                'a'..='z' | 'A'..='Z' | '0'..='9' => c.to_ascii_uppercase(),
+                _ => '_',
+            })
+            .collect::<String>();
+
+        env.push((format!("{}ATTR_{}", ENV_PREFIX, name), value.clone()));
+    }
+
+    env.sort();
+
+    env
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use bollard::models::{EventActor, EventMessageTypeEnum};
+    use pretty_assertions::assert_eq;
+
+    use crate::docker_test;
+
+    use super::*;
+
+    #[test]
+    fn test_event_env() {
+        let event = EventMessage {
+            typ: Some(EventMessageTypeEnum::CONTAINER),
+            action: Some("die".to_string()),
+            actor: Some(EventActor {
+                id: Some("abc".to_string()),
+                attributes: Some(HashMap::from([
+                    ("name".to_string(), "web".to_string()),
+                    ("com.example.team".to_string(), "shop".to_string()),
+                ])),
+            }),
+            time: Some(1682935200),
+            ..Default::default()
+        };
+
+        let env = [
+            ("DOCKRS_EVENT_ACTION", "die"),
+            ("DOCKRS_EVENT_ATTR_COM_EXAMPLE_TEAM", "shop"),
+            ("DOCKRS_EVENT_ATTR_NAME", "web"),
+            ("DOCKRS_EVENT_ID", "abc"),
+            ("DOCKRS_EVENT_TIME", "1682935200"),
+            ("DOCKRS_EVENT_TYPE", "container"),
+        ]
+        .map(|(name, value)| (name.to_string(), value.to_string()));
+
+        assert_eq!(event_env(&event), env);
+    }
+
+    #[tokio::test]
+    async fn test_events() {
+        let docker = docker_test!({
+            use crate::mock::MockDocker;
+
+            let mut mock = MockDocker::new();
+
+            mock.expect_events()
+                .return_once(|_| Box::pin(futures::stream::empty()));
+
+            mock
+        });
+
+        let events = events(&docker, EventsOptions::default())
+            .collect::<Vec<_>>()
+            .await;
+
+        assert!(events.is_empty(), "unexpected events {:?}", events);
+    }
+}
@@ -0,0 +1,658 @@
+use std::{
+    env,
+    error::Error as StdError,
+    fmt::Display,
+    io,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+#[cfg(unix)]
+use std::{
+    fs::DirBuilder,
+    os::unix::fs::DirBuilderExt,
+    process::Stdio,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bollard::API_DEFAULT_VERSION;
+#[cfg(unix)]
+use tokio::{
+    io::AsyncWriteExt,
+    net::{UnixListener, UnixStream},
+    process::Command,
+};
+use tracing::debug;
+#[cfg(unix)]
+use tracing::error;
+
+use crate::error::{bail, ensure, Error, Result};
+#[cfg(feature = "mock")]
+use crate::mock::{DockerTrait, MockDocker as Docker};
+#[cfg(not(feature = "mock"))]
+use bollard::Docker;
+
+/// Default timeout of the requests to the daemon.
+pub(crate) const DEFAULT_TIMEOUT: Duration = Duration::from_secs(120);
+#[cfg(unix)]
+const DEFAULT_SOCKET: &str = "/var/run/docker.sock";
+#[cfg(windows)]
+const DEFAULT_NAMED_PIPE: &str = "//./pipe/docker_engine";
+const DEFAULT_HTTP_PORT: u16 = 2375;
+const DEFAULT_TLS_PORT: u16 = 2376;
+
+/// A daemon endpoint, as given with `--host` or `DOCKER_HOST`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Host {
+    /// A unix socket (e.g. `unix:///var/run/docker.sock`).
+    Unix(String),
+    /// A Windows named pipe (e.g. `npipe:////./pipe/docker_engine`).
+    #[cfg(windows)]
+    NamedPipe(String),
+    /// A plain TCP endpoint (e.g. `tcp://host:2375` or `http://host:2375`).
+    Http(String),
+    /// A TCP endpoint with TLS, using the `ca.pem` of a directory to verify the daemon, and its
+    /// `key.pem` and `cert.pem` if any to authenticate the client.
+    Tls { addr: String, cert_path: PathBuf },
+    /// A remote daemon reached with `ssh [user@]host docker system dial-stdio`.
+    Ssh {
+        destination: String,
+        port: Option<u16>,
+    },
+}
+
+impl Host {
+    /// Parse a host, `tcp://` endpoints using TLS when a certificates directory is given.
+    pub(crate) fn parse(input: &str, tls_cert_path: Option<&Path>) -> Result<Self> {
+        let Some((scheme, address)) = input.split_once("://") else {
+            bail!(
+                Parse,
+                "Invalid host {}: missing scheme (e.g. unix://, tcp://)",
+                input
+            );
+        };
+
+        ensure!(
+            !address.is_empty(),
+            Parse,
+            "Invalid host {}: missing address",
+            input
+        );
+
+        let host = match (scheme, tls_cert_path) {
+            ("unix", _) => Self::Unix(address.to_string()),
+            #[cfg(windows)]
+            ("npipe", _) => Self::NamedPipe(address.to_string()),
+            ("tcp", None) | ("http", _) => {
+                Self::Http(with_default_port(address, DEFAULT_HTTP_PORT)?)
+            }
+            ("tcp", Some(cert_path)) => Self::Tls {
+                addr: with_default_port(address, DEFAULT_TLS_PORT)?,
+                cert_path: cert_path.to_path_buf(),
+            },
+            ("https", cert_path) => Self::Tls {
+                addr: with_default_port(address, DEFAULT_TLS_PORT)?,
+                cert_path: match cert_path {
+                    Some(cert_path) => cert_path.to_path_buf(),
+                    None => default_cert_path()?,
+                },
+            },
+            ("ssh", _) => {
+                let (destination, port) = match address.rsplit_once(':') {
+                    Some((destination, port)) => {
+                        let port = port
+                            .parse()
+                            .map_err(|_| Error::Parse(format!("Invalid port in host {}", input)))?;
+
+                        (destination, Some(port))
+                    }
+                    None => (address, None),
+                };
+
+                ensure!(
+                    !destination.contains('/') && !destination.ends_with('@'),
+                    Parse,
+                    "Invalid host {}: expected ssh://[user@]host[:port]",
+                    input
+                );
+
+                // They would be options of ssh
+                let (user, hostname) = match destination.rsplit_once('@') {
+                    Some((user, hostname)) => (Some(user), hostname),
+                    None => (None, destination),
+                };
+
+                ensure!(
+                    !hostname.starts_with('-') && !user.is_some_and(|user| user.starts_with('-')),
+                    Parse,
+                    "Invalid host {}: the user and host can't start with '-'",
+                    input
+                );
+
+                Self::Ssh {
+                    destination: destination.to_string(),
+                    port,
+                }
+            }
+            _ => bail!(
+                Parse,
+                "Invalid host {}: unsupported scheme {}",
+                input,
+                scheme
+            ),
+        };
+
+        Ok(host)
+    }
+
+    /// The first existing local socket, of rootful Docker, rootless Docker or rootless Podman,
+    /// defaulting to the rootful Docker socket.
+    #[cfg(unix)]
+    pub(crate) fn local() -> Self {
+        let runtime_dir = env::var_os("XDG_RUNTIME_DIR").map(PathBuf::from);
+
+        let candidates = local_sockets(runtime_dir.as_deref());
+
+        let socket = candidates
+            .iter()
+            .find(|socket| {
+                let exists = socket.exists();
+
+                debug!(socket = %socket.display(), exists, "Probing local socket");
+
+                exists
+            })
+            .unwrap_or(&candidates[0]);
+
+        debug!(socket = %socket.display(), "Using local socket");
+
+        Self::Unix(socket.to_string_lossy().into_owned())
+    }
+
+    /// The named pipe of Docker Desktop.
+    #[cfg(windows)]
+    pub(crate) fn local() -> Self {
+        Self::NamedPipe(DEFAULT_NAMED_PIPE.to_string())
+    }
+
+    /// Create a client for the host, without connecting to the daemon.
+    pub(crate) fn connect(&self, timeout: Duration) -> Result<Docker> {
+        debug!(host = %self, ?timeout, "Connecting to the daemon");
+
+        let timeout = timeout_secs(timeout)?;
+
+        let docker = match self {
+            #[cfg(unix)]
+            Self::Unix(path) => Docker::connect_with_unix(path, timeout, API_DEFAULT_VERSION)?,
+            #[cfg(not(unix))]
+            Self::Unix(_) => bail!(Invalid, "unix:// hosts are only supported on unix"),
+            #[cfg(windows)]
+            Self::NamedPipe(path) => {
+                Docker::connect_with_named_pipe(path, timeout, API_DEFAULT_VERSION)?
+            }
+            Self::Http(addr) => Docker::connect_with_http(addr, timeout, API_DEFAULT_VERSION)?,
+            Self::Tls { addr, cert_path } => Docker::connect_with_ssl(
+                addr,
+                // Without them the client doesn't send a certificate
+                &check_tls_dir(cert_path)?.join("key.pem"),
+                &cert_path.join("cert.pem"),
+                &cert_path.join("ca.pem"),
+                timeout,
+                API_DEFAULT_VERSION,
+            )
+            .map_err(|e| {
+                ConnectionError(format!(
+                    "Failed to load the TLS certificates in {}: {}",
+                    cert_path.display(),
+                    e
+                ))
+            })?,
+            #[cfg(unix)]
+            Self::Ssh { destination, port } => {
+                let socket = ssh_tunnel(destination, *port)?;
+
+                Docker::connect_with_unix(&socket.to_string_lossy(), timeout, API_DEFAULT_VERSION)?
+            }
+            #[cfg(not(unix))]
+            Self::Ssh { .. } => bail!(Invalid, "ssh:// hosts are only supported on unix"),
+        };
+
+        Ok(docker)
+    }
+
+    /// Turn an error of the first request to the daemon into a [`ConnectionError`] when the
+    /// daemon couldn't be reached.
+    pub(crate) fn connection_error(&self, error: bollard::errors::Error) -> Error {
+        let kind = match &error {
+            bollard::errors::Error::IOError { err } => Some(err.kind()),
+            error => std::iter::successors(Some(error as &dyn StdError), |error| (*error).source())
+                .find_map(|error| error.downcast_ref::<io::Error>())
+                .map(io::Error::kind),
+        };
+
+        let message = match (&error, kind) {
+            (_, Some(io::ErrorKind::PermissionDenied)) => format!(
+                "Permission denied while trying to connect to the Docker daemon at {}, \
+                 are you in the docker group?",
+                self
+            ),
+            (bollard::errors::Error::RequestTimeoutError, _) => {
+                format!("Timed out connecting to the Docker daemon at {}", self)
+            }
+            (
+                bollard::errors::Error::HyperResponseError { .. }
+                | bollard::errors::Error::IOError { .. },
+                _,
+            ) => format!(
+                "Cannot connect to the Docker daemon at {}. Is it running?",
+                self
+            ),
+            // The daemon was reached, but answered with an error
+            _ => return error.into(),
+        };
+
+        ConnectionError(message).into()
+    }
+}
+
+impl Display for Host {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Unix(path) => write!(f, "unix://{}", path),
+            #[cfg(windows)]
+            Self::NamedPipe(path) => write!(f, "npipe://{}", path),
+            Self::Http(addr) | Self::Tls { addr, .. } => write!(f, "tcp://{}", addr),
+            Self::Ssh {
+                destination,
+                port: Some(port),
+            } => write!(f, "ssh://{}:{}", destination, port),
+            Self::Ssh { destination, .. } => write!(f, "ssh://{}", destination),
+        }
+    }
+}
+
+/// The daemon couldn't be reached, the message is meant to be shown as is to the user.
+#[derive(Debug)]
+pub struct ConnectionError(String);
+
+impl Display for ConnectionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl StdError for ConnectionError {}
+
+/// The local sockets to probe, in order of preference.
+#[cfg(unix)]
+fn local_sockets(runtime_dir: Option<&Path>) -> Vec<PathBuf> {
+    let mut sockets = vec![PathBuf::from(DEFAULT_SOCKET)];
+
+    if let Some(runtime_dir) = runtime_dir.filter(|dir| !dir.as_os_str().is_empty()) {
+        sockets.push(runtime_dir.join("docker.sock"));
+        sockets.push(runtime_dir.join("podman").join("podman.sock"));
+    }
+
+    sockets
+}
+
+/// The timeout in the whole seconds expected by the client, rounding a sub-second part up.
+fn timeout_secs(timeout: Duration) -> Result<u64> {
+    ensure!(
+        !timeout.is_zero(),
+        Invalid,
+        "Invalid timeout: it must be greater than 0"
+    );
+
+    Ok(timeout.as_secs() + u64::from(timeout.subsec_nanos() > 0))
+}
+
+/// The certificates directory to use for TCP endpoints, if `DOCKER_TLS_VERIFY` is set.
+///
+/// The certificates are read from `DOCKER_CERT_PATH`, or `~/.docker`.
+pub(crate) fn tls_cert_path() -> Result<Option<PathBuf>> {
+    let verify = env::var_os("DOCKER_TLS_VERIFY").unwrap_or_default();
+
+    if verify.is_empty() {
+        return Ok(None);
+    }
+
+    match env::var_os("DOCKER_CERT_PATH") {
+        Some(path) if !path.is_empty() => Ok(Some(PathBuf::from(path))),
+        _ => default_cert_path().map(Some),
+    }
+}
+
+fn default_cert_path() -> Result<PathBuf> {
+    let home = dirs_next::home_dir().ok_or_else(|| {
+        Error::io("Failed to find the home directory")(io::ErrorKind::NotFound.into())
+    })?;
+
+    Ok(home.join(".docker"))
+}
+
+/// Checks the TLS material of a directory: the CA is required to verify the daemon, the client
+/// key and certificate are optional but go together.
+fn check_tls_dir(cert_path: &Path) -> Result<&Path> {
+    ensure!(
+        cert_path.join("ca.pem").exists(),
+        Invalid,
+        "Missing ca.pem in {} to verify the daemon",
+        cert_path.display()
+    );
+    ensure!(
+        cert_path.join("key.pem").exists() == cert_path.join("cert.pem").exists(),
+        Invalid,
+        "Missing key.pem or cert.pem in {}: both are needed to authenticate the client",
+        cert_path.display()
+    );
+
+    Ok(cert_path)
+}
+
+/// Add the default port to an address without a port (e.g. `localhost`).
+fn with_default_port(address: &str, port: u16) -> Result<String> {
+    let address = address.trim_end_matches('/');
+
+    ensure!(
+        !address.contains('/'),
+        Parse,
+        "Invalid address {}: paths are not supported",
+        address
+    );
+
+    // Bracketed IPv6 addresses contain colons but only have a port after the closing bracket
+    let host_end = address.rfind(']').unwrap_or(0);
+
+    if address[host_end..].contains(':') {
+        Ok(address.to_string())
+    } else {
+        Ok(format!("{}:{}", address, port))
+    }
+}
+
+/// A private directory removed with its content when dropped.
+#[cfg(unix)]
+struct TunnelDir(PathBuf);
+
+#[cfg(unix)]
+impl TunnelDir {
+    /// Creates a new directory only accessible by the user, in `XDG_RUNTIME_DIR` or the temporary
+    /// directory, failing if it already exists.
+    fn create() -> Result<Self> {
+        let parent = env::var_os("XDG_RUNTIME_DIR")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .unwrap_or_else(env::temp_dir);
+
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .subsec_nanos();
+
+        let path = parent.join(format!("dockrs-{}-{:08x}", std::process::id(), nanos));
+
+        DirBuilder::new()
+            .mode(0o700)
+            .create(&path)
+            .map_err(Error::io(format!("Failed to create {}", path.display())))?;
+
+        Ok(Self(path))
+    }
+}
+
+#[cfg(unix)]
+impl Drop for TunnelDir {
+    fn drop(&mut self) {
+        debug!(dir = %self.0.display(), "Removing the ssh tunnel directory");
+
+        if let Err(e) = std::fs::remove_dir_all(&self.0) {
+            error!(?e, "Failed to remove the ssh tunnel directory");
+        }
+    }
+}
+
+/// Listens on a private unix socket, forwarding each connection to a
+/// `docker system dial-stdio` command run over ssh.
+///
+/// The socket and its directory are removed once the tunnel stops, with the runtime.
+#[cfg(unix)]
+fn ssh_tunnel(destination: &str, port: Option<u16>) -> Result<PathBuf> {
+    let dir = TunnelDir::create()?;
+    let socket = dir.0.join("ssh.sock");
+
+    let listener = UnixListener::bind(&socket).map_err(Error::io(format!(
+        "Failed to listen on {}",
+        socket.display()
+    )))?;
+
+    let mut args = vec!["-T".to_string()];
+
+    if let Some(port) = port {
+        args.extend(["-p".to_string(), port.to_string()]);
+    }
+
+    args.extend(["--", destination, "docker", "system", "dial-stdio"].map(str::to_string));
+
+    tokio::spawn(async move {
+        // Owned by the task to be removed when it stops
+        let _dir = dir;
+
+        loop {
+            let stream = match listener.accept().await {
+                Ok((stream, _)) => stream,
+                Err(e) => {
+                    error!(?e, "Failed to accept a connection to the ssh tunnel");
+                    break;
+                }
+            };
+
+            let args = args.clone();
+
+            tokio::spawn(async move {
+                if let Err(e) = dial_stdio(stream, &args).await {
+                    error!(?e, "Failed to forward the connection over ssh");
+                }
+            });
+        }
+    });
+
+    Ok(socket)
+}
+
+#[cfg(unix)]
+async fn dial_stdio(stream: UnixStream, args: &[String]) -> Result<()> {
+    debug!(?args, "Running ssh");
+
+    let mut child = Command::new("ssh")
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(Error::io("Failed to run ssh"))?;
+
+    let mut stdin = child
+        .stdin
+        .take()
+        .ok_or_else(|| Error::Invalid("Failed to open ssh stdin".to_string()))?;
+    let mut stdout = child
+        .stdout
+        .take()
+        .ok_or_else(|| Error::Invalid("Failed to open ssh stdout".to_string()))?;
+
+    let (mut reader, mut writer) = stream.into_split();
+
+    tokio::try_join!(
+        async {
+            tokio::io::copy(&mut reader, &mut stdin).await?;
+            stdin.shutdown().await
+        },
+        tokio::io::copy(&mut stdout, &mut writer),
+    )?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_parse_host() {
+        let certs = Path::new("/certs");
+
+        let cases = [
+            (
+                "unix:///var/run/docker.sock",
+                None,
+                Host::Unix("/var/run/docker.sock".to_string()),
+            ),
+            (
+                "tcp://10.0.0.1",
+                None,
+                Host::Http("10.0.0.1:2375".to_string()),
+            ),
+            (
+                "http://build:8080/",
+                Some(certs),
+                Host::Http("build:8080".to_string()),
+            ),
+            (
+                "tcp://[::1]",
+                Some(certs),
+                Host::Tls {
+                    addr: "[::1]:2376".to_string(),
+                    cert_path: certs.to_path_buf(),
+                },
+            ),
+            (
+                "ssh://deploy@build.example.com:2222",
+                None,
+                Host::Ssh {
+                    destination: "deploy@build.example.com".to_string(),
+                    port: Some(2222),
+                },
+            ),
+            (
+                "ssh://build",
+                None,
+                Host::Ssh {
+                    destination: "build".to_string(),
+                    port: None,
+                },
+            ),
+        ];
+
+        for (input, cert_path, expected) in cases {
+            assert_eq!(
+                Host::parse(input, cert_path).unwrap(),
+                expected,
+                "{}",
+                input
+            );
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_local_sockets() {
+        assert_eq!(local_sockets(None), [PathBuf::from("/var/run/docker.sock")]);
+        assert_eq!(
+            local_sockets(Some(Path::new("/run/user/1000"))),
+            [
+                PathBuf::from("/var/run/docker.sock"),
+                PathBuf::from("/run/user/1000/docker.sock"),
+                PathBuf::from("/run/user/1000/podman/podman.sock"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_connection_error() {
+        let host = Host::Unix("/var/run/docker.sock".to_string());
+
+        let error = bollard::errors::Error::IOError {
+            err: io::Error::from(io::ErrorKind::PermissionDenied),
+        };
+
+        let error = host.connection_error(error);
+
+        assert!(matches!(error, Error::Connection(_)));
+        assert_eq!(
+            error.to_string(),
+            "Permission denied while trying to connect to the Docker daemon at \
+             unix:///var/run/docker.sock, are you in the docker group?"
+        );
+
+        let error = bollard::errors::Error::IOError {
+            err: io::Error::from(io::ErrorKind::NotFound),
+        };
+
+        assert_eq!(
+            host.connection_error(error).to_string(),
+            "Cannot connect to the Docker daemon at unix:///var/run/docker.sock. Is it running?"
+        );
+
+        let error = bollard::errors::Error::DockerResponseServerError {
+            status_code: 500,
+            message: "oops".to_string(),
+        };
+
+        assert!(matches!(host.connection_error(error), Error::Docker(_)));
+    }
+
+    #[test]
+    fn test_check_tls_dir() {
+        let dir = env::temp_dir().join("dockrs-test-tls");
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let missing_ca = check_tls_dir(&dir).is_err();
+
+        // Only verifying the daemon
+        std::fs::write(dir.join("ca.pem"), "ca").unwrap();
+        let ca_only = check_tls_dir(&dir).is_ok();
+
+        std::fs::write(dir.join("cert.pem"), "cert").unwrap();
+        let missing_key = check_tls_dir(&dir).is_err();
+
+        std::fs::write(dir.join("key.pem"), "key").unwrap();
+        let client = check_tls_dir(&dir).is_ok();
+
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert!(missing_ca);
+        assert!(ca_only);
+        assert!(missing_key);
+        assert!(client);
+    }
+
+    #[test]
+    fn test_parse_host_invalid() {
+        let cases = [
+            "localhost:2375",
+            "tcp://",
+            "tcp://host/path",
+            "ssh://host:port",
+            "ssh://host/path",
+            "ssh://-oProxyCommand=reboot",
+            "ssh://-luser@host",
+            "ssh://user@-oProxyCommand=reboot:22",
+        ];
+
+        for input in cases {
+            assert!(Host::parse(input, None).is_err(), "{}", input);
+        }
+
+        #[cfg(not(windows))]
+        assert!(Host::parse("npipe:////./pipe/docker_engine", None).is_err());
+    }
+
+    #[test]
+    fn test_timeout_secs() {
+        assert_eq!(timeout_secs(Duration::from_secs(120)).unwrap(), 120);
+        assert_eq!(timeout_secs(Duration::from_millis(500)).unwrap(), 1);
+        assert_eq!(timeout_secs(Duration::from_millis(1500)).unwrap(), 2);
+        assert!(timeout_secs(Duration::ZERO).is_err());
+    }
+}
@@ -1,488 +1,991 @@
-use std::{collections::HashMap, fmt::Display, ops::Deref};
+use std::{
+    collections::HashMap,
+    env,
+    fmt::Display,
+    fs,
+    net::{IpAddr, Ipv6Addr},
+    num::NonZeroUsize,
+    ops::Deref,
+    pin::Pin,
+    time::Duration,
+};
 
 use bollard::{
     container::{
-        AttachContainerOptions, AttachContainerResults, Config, CreateContainerOptions, LogOutput,
-        LogsOptions, RemoveContainerOptions, StartContainerOptions,
+        AttachContainerOptions, AttachContainerResults, Config, CreateContainerOptions,
+        KillContainerOptions, LogOutput, RemoveContainerOptions, StartContainerOptions,
+        StopContainerOptions, WaitContainerOptions,
     },
     image::{CreateImageOptions, RemoveImageOptions},
-    service::PortBinding,
-    system::EventsOptions,
+    models::CreateImageInfo,
+    service::{
+        DeviceMapping, ImageDeleteResponseItem, PortBinding, RestartPolicy, RestartPolicyNameEnum,
+    },
 };
-use chrono::Local;
-use color_eyre::{eyre::ensure, eyre::ContextCompat, Result};
-use futures::{future::join_all, StreamExt};
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use clap::ValueEnum;
+use futures::{stream::BoxStream, StreamExt};
 
 #[cfg(not(feature = "mock"))]
 use bollard::Docker;
 #[cfg(feature = "mock")]
 use mock::{DockerTrait, MockDocker as Docker};
 
-pub use list::list;
-pub use stats::stats;
-
+use auth::{normalize_registry, DockerConfig};
+pub use batch::{Batch, Outcome, DEFAULT_PARALLEL};
+pub use context::{context_create, context_ls, context_rm, context_show, context_use, ContextRow};
+use context::{current_context, ContextStore};
+use error::{bail, ensure};
+pub use error::{Error, Result};
+pub use events::{events, spawn_event_command};
+pub use host::ConnectionError;
+use host::{tls_cert_path, Host, DEFAULT_TIMEOUT};
+pub use list::{list, ContainerRow, Size};
+pub use logs::{logs, LogEvent};
+pub use reference::Reference;
+use reference::{is_image_id, DEFAULT_REGISTRY};
+pub use stats::{stats, StatsEvent};
+pub use template::render as render_template;
+
+mod auth;
+mod batch;
 pub mod cli;
+mod context;
+mod error;
+mod events;
+mod host;
 mod list;
+mod logs;
 #[cfg(feature = "mock")]
 mod mock;
+mod mount;
+mod reference;
 mod stats;
+mod template;
+
+use tokio::io::AsyncWrite;
+use tracing::{instrument, warn};
+
+/// Connect to the daemon of `host`, or of `context`, or of `DOCKER_HOST`, or of the current
+/// context (`DOCKER_CONTEXT` or the configuration). The default context uses `DOCKER_HOST` if set,
+/// falling back to the local daemon.
+///
+/// TCP hosts use TLS when `DOCKER_TLS_VERIFY` is set, with the certificates of
+/// `DOCKER_CERT_PATH`.
+///
+/// The daemon is pinged and the API version negotiated, failing with a [`ConnectionError`] if
+/// the daemon can't be reached.
+pub async fn connect_to_docker(
+    host: Option<&str>,
+    context: Option<&str>,
+    timeout: Option<Duration>,
+) -> Result<Docker> {
+    let env_host = env::var("DOCKER_HOST").ok().filter(|host| !host.is_empty());
+
+    let host = resolve_host(host, context, env_host.as_deref())?;
+    let docker = host.connect(timeout.unwrap_or(DEFAULT_TIMEOUT))?;
+
+    if let Err(e) = docker.ping().await {
+        return Err(host.connection_error(e));
+    }
 
-use tokio::{
-    io::{stderr, stdout, AsyncBufReadExt, AsyncWriteExt},
-    task::JoinHandle,
-};
-use tracing::{error, instrument, warn};
+    docker
+        .negotiate_version()
+        .await
+        .map_err(|e| host.connection_error(e))
+}
+
+/// The host to connect to, the default context using `DOCKER_HOST` if set.
+fn resolve_host(host: Option<&str>, context: Option<&str>, env_host: Option<&str>) -> Result<Host> {
+    let context = match (host, context, env_host) {
+        (Some(host), _, _) | (None, None, Some(host)) => {
+            return Host::parse(host, tls_cert_path()?.as_deref());
+        }
+        (None, Some(context), _) => context.to_string(),
+        (None, None, None) => current_context(&DockerConfig::load(&auth::config_path()?)?),
+    };
+
+    match (ContextStore::open()?.host(&context)?, env_host) {
+        (Some(host), _) => Ok(host),
+        (None, Some(host)) => Host::parse(host, tls_cert_path()?.as_deref()),
+        (None, None) => Ok(Host::local()),
+    }
+}
+
+/// Parse a port or a range of ports (e.g. `80` or `8000-8010`).
+fn parse_port_range(input: &str) -> Result<(u16, u16)> {
+    let (start, end) = input.split_once('-').unwrap_or((input, input));
+
+    let start: u16 = start
+        .parse()
+        .map_err(|_| Error::Parse(format!("Invalid port {}", input)))?;
+    let end: u16 = end
+        .parse()
+        .map_err(|_| Error::Parse(format!("Invalid port {}", input)))?;
+
+    ensure!(start <= end, Parse, "Invalid port range {}", input);
+
+    Ok((start, end))
+}
+
+/// Split the protocol from a port specification, defaulting to `tcp`.
+fn split_protocol(input: &str) -> Result<(&str, &str)> {
+    let (ports, protocol) = input.rsplit_once('/').unwrap_or((input, "tcp"));
 
-pub fn connect_to_docker() -> Result<Docker> {
-    let docker = Docker::connect_with_local_defaults()?;
+    ensure!(
+        matches!(protocol, "tcp" | "udp" | "sctp"),
+        Parse,
+        "Invalid protocol {} in {}",
+        protocol,
+        input
+    );
 
-    Ok(docker)
+    Ok((ports, protocol))
 }
 
+/// Parse port bindings in the form `[ip:][host-port:]container-port[/protocol]`.
+///
+/// Ports can be ranges (e.g. `8000-8010:8000-8010`) and IPv6 addresses must be enclosed in
+/// brackets (e.g. `[::1]:80:80`). The keys are normalized in the `port/protocol` form.
 pub fn get_port_bindings<T: Deref<Target = str> + Display>(
     input: &[T],
-) -> color_eyre::Result<HashMap<String, Option<Vec<PortBinding>>>> {
+) -> Result<HashMap<String, Option<Vec<PortBinding>>>> {
     let mut bindings = HashMap::new();
 
     for input in input {
-        let mut parts = input.rsplitn(3, ':');
+        let (ports, protocol) = split_protocol(input)?;
+
+        let (host_ip, ports) = match ports.strip_prefix('[') {
+            Some(ports) => {
+                let (ip, ports) = ports
+                    .split_once("]:")
+                    .ok_or_else(|| Error::Parse(format!("Invalid port binding {}", input)))?;
+
+                ensure!(
+                    ip.parse::<Ipv6Addr>().is_ok(),
+                    Parse,
+                    "Invalid IPv6 address {} in {}",
+                    ip,
+                    input
+                );
+
+                (Some(ip), ports)
+            }
+            None => {
+                let parts: Vec<&str> = ports.splitn(3, ':').collect();
 
-        let first = parts
-            .next()
-            .wrap_err_with(|| format!("Invalid port binding {}", input))?;
+                match parts.as_slice() {
+                    [ip, _, _] => (Some(*ip), &ports[ip.len() + 1..]),
+                    _ => (None, ports),
+                }
+            }
+        };
 
-        let second = parts.next();
-        let third = parts.next();
+        let (host_ports, container_ports) = match ports.split_once(':') {
+            Some((host, container)) => (Some(host), container),
+            None => (None, ports),
+        };
 
-        let entry = bindings
-            .entry(first.to_string())
-            .or_insert_with(|| Some(vec![]));
+        ensure!(
+            !container_ports.contains(':'),
+            Parse,
+            "Invalid port binding {}",
+            input
+        );
 
-        match (second, third) {
-            (None, None) => {}
-            (Some(second), None) => {
-                entry.as_mut().unwrap().push(PortBinding {
-                    host_ip: None,
-                    host_port: Some(second.to_string()),
-                });
-            }
-            (Some(second), Some(third)) => {
-                entry.as_mut().unwrap().push(PortBinding {
-                    host_ip: Some(third.to_string()),
-                    host_port: Some(second.to_string()),
-                });
+        let (start, end) = parse_port_range(container_ports)?;
+
+        let host_ports = host_ports
+            .filter(|host| !host.is_empty())
+            .map(parse_port_range)
+            .transpose()?;
+
+        let host_ip = host_ip.filter(|ip| !ip.is_empty());
+
+        for (i, port) in (start..=end).enumerate() {
+            let key = format!("{}/{}", port, protocol);
+
+            let host_port = match host_ports {
+                // A single container port can be bound to any port of a host range
+                Some((host_start, host_end)) if start == end => {
+                    if host_start == host_end {
+                        Some(host_start.to_string())
+                    } else {
+                        Some(format!("{}-{}", host_start, host_end))
+                    }
+                }
+                Some((host_start, host_end)) => {
+                    ensure!(
+                        host_end - host_start == end - start,
+                        Parse,
+                        "Invalid port binding {}: host and container port ranges must have the same length",
+                        input
+                    );
+
+                    Some((host_start + i as u16).to_string())
+                }
+                None => None,
+            };
+
+            let entry = bindings.entry(key).or_insert_with(|| Some(vec![]));
+
+            if host_port.is_none() && host_ip.is_none() {
+                continue;
             }
-            _ => unreachable!("Invalid port binding {}", input),
+
+            entry.get_or_insert_with(Vec::new).push(PortBinding {
+                host_ip: host_ip.map(str::to_string),
+                host_port,
+            });
         }
     }
 
     Ok(bindings)
 }
 
+/// Parse exposed ports in the form `port[-end-port][/protocol]`, normalized in the `port/protocol`
+/// form.
+pub fn get_exposed_ports<T: Deref<Target = str> + Display>(
+    input: &[T],
+) -> Result<HashMap<String, HashMap<(), ()>>> {
+    let mut exposed = HashMap::new();
+
+    for input in input {
+        let (ports, protocol) = split_protocol(input)?;
+
+        let (start, end) = parse_port_range(ports)?;
+
+        for port in start..=end {
+            exposed.insert(format!("{}/{}", port, protocol), HashMap::new());
+        }
+    }
+
+    Ok(exposed)
+}
+
 pub fn parse_filter(input: &str) -> Result<(&str, &str)> {
     let mut parts = input.splitn(2, '=');
 
     let filter = parts
         .next()
-        .wrap_err_with(|| format!("Invalid filter {}", input))?;
+        .ok_or_else(|| Error::Parse(format!("Invalid filter {}", input)))?;
 
     let value = parts
         .next()
-        .wrap_err_with(|| format!("Invalid filter {}", input))?;
+        .ok_or_else(|| Error::Parse(format!("Invalid filter {}", input)))?;
 
     Ok((filter, value))
 }
 
-async fn attach_container(
-    docker: &Docker,
-    container: &str,
-    interactive: bool,
-) -> Result<Vec<JoinHandle<Result<()>>>> {
-    let options = AttachContainerOptions::<&str> {
-        stream: Some(true),
-        stdin: Some(interactive),
-        stdout: Some(true),
-        stderr: Some(true),
-        ..Default::default()
-    };
+/// Parse a duration in the format used by the docker CLI (e.g. `1m30s`, `500ms`, `1.5h`).
+pub fn parse_duration(input: &str) -> Result<Duration> {
+    ensure!(!input.is_empty(), Parse, "Invalid duration {}", input);
 
-    let AttachContainerResults {
-        mut output,
-        mut input,
-    } = docker.attach_container(container, Some(options)).await?;
-
-    let join: JoinHandle<Result<()>> = tokio::spawn(async move {
-        let mut stdout = stdout();
-        let mut stderr = stderr();
-
-        while let Some(chunk) = output.next().await {
-            match chunk? {
-                LogOutput::StdOut { message } => stdout.write(&message).await?,
-                LogOutput::StdErr { message } => stderr.write(&message).await?,
-                LogOutput::StdIn { .. } => unreachable!("We didn't ask for stdin"),
-                LogOutput::Console { message } => stdout.write(&message).await?,
-            };
+    if input == "0" {
+        return Ok(Duration::ZERO);
+    }
 
-            stdout.flush().await?;
-            stderr.flush().await?;
-        }
+    let mut total = Duration::ZERO;
+    let mut rest = input;
 
-        Ok(())
-    });
+    while !rest.is_empty() {
+        let value_end = rest
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .ok_or_else(|| Error::Parse(format!("Missing unit in duration {}", input)))?;
 
-    if interactive {
-        let out: JoinHandle<Result<()>> = tokio::spawn(async move {
-            let mut buf = String::new();
-            let mut stdin = tokio::io::BufReader::new(tokio::io::stdin());
+        let (value, tail) = rest.split_at(value_end);
 
-            loop {
-                stdin.read_line(&mut buf).await?;
-                input.write_all(buf.as_bytes()).await?;
-            }
-        });
+        let unit_end = tail
+            .find(|c: char| c.is_ascii_digit() || c == '.')
+            .unwrap_or(tail.len());
+
+        let (unit, tail) = tail.split_at(unit_end);
 
-        return Ok(vec![join, out]);
+        let value: f64 = value
+            .parse()
+            .map_err(|_| Error::Parse(format!("Invalid duration {}", input)))?;
+
+        let secs = match unit {
+            "ns" => 1e-9,
+            "us" | "µs" => 1e-6,
+            "ms" => 1e-3,
+            "s" => 1.0,
+            "m" => 60.0,
+            "h" => 3600.0,
+            _ => bail!(Parse, "Unknown unit {} in duration {}", unit, input),
+        };
+
+        total = Duration::try_from_secs_f64(value * secs)
+            .ok()
+            .and_then(|duration| total.checked_add(duration))
+            .ok_or_else(|| Error::Parse(format!("Duration {} is out of range", input)))?;
+        rest = tail;
     }
 
-    Ok(vec![join])
+    Ok(total)
 }
 
-#[instrument(skip(options, config))]
-pub async fn run(
-    docker: &Docker,
-    options: Option<CreateContainerOptions<&str>>,
-    config: Config<&str>,
-    rm: bool,
-) -> Result<()> {
-    let container = docker.create_container(options, config).await?;
+/// Parse a timestamp as RFC 3339, Unix timestamp (e.g. `1700000000.5`), local date and time (e.g.
+/// `2023-05-01T12:00:00` or `2023-05-01`) or duration relative to `now` (e.g. `10m`).
+///
+/// The fractional seconds are kept, callers needing whole seconds round in the direction that
+/// keeps the requested window.
+pub fn parse_timestamp(input: &str, now: DateTime<Utc>) -> Result<DateTime<Utc>> {
+    let invalid = || Error::Parse(format!("Invalid timestamp {}", input));
 
-    if !container.warnings.is_empty() {
-        warn!("Warnings while creating the container");
-        for warning in container.warnings {
-            warn!(?warning);
-        }
+    if let Ok(time) = DateTime::parse_from_rfc3339(input) {
+        return Ok(time.with_timezone(&Utc));
     }
 
-    let join = attach_container(docker, &container.id, false).await?;
+    if !input.is_empty() && input.chars().all(|c| c.is_ascii_digit() || c == '.') {
+        let (secs, fraction) = input.split_once('.').unwrap_or((input, ""));
 
-    docker
-        .start_container(&container.id, None::<StartContainerOptions<&str>>)
-        .await?;
+        let secs: i64 = secs.parse().map_err(|_| invalid())?;
+        // Nanoseconds, ignoring the digits after them
+        let nanos: u32 = format!("{:0<9}", fraction.get(..9).unwrap_or(fraction))
+            .parse()
+            .map_err(|_| invalid())?;
+
+        return Utc.timestamp_opt(secs, nanos).single().ok_or_else(invalid);
+    }
 
-    join_all(join).await;
+    let local = NaiveDateTime::parse_from_str(input, "%Y-%m-%dT%H:%M:%S")
+        .or_else(|_| {
+            NaiveDate::parse_from_str(input, "%Y-%m-%d")
+                .map(|date| date.and_hms_opt(0, 0, 0).unwrap_or_default())
+        })
+        .ok()
+        .and_then(|time| time.and_local_timezone(Local).earliest());
 
-    if rm {
-        docker.remove_container(&container.id, None).await?;
+    if let Some(time) = local {
+        return Ok(time.with_timezone(&Utc));
     }
 
-    Ok(())
+    parse_duration(input)
+        .ok()
+        .and_then(|duration| chrono::Duration::from_std(duration).ok())
+        .and_then(|duration| now.checked_sub_signed(duration))
+        .ok_or_else(invalid)
 }
 
-pub async fn pull(docker: &Docker, image: &str, tag: &str) -> Result<()> {
-    let options = CreateImageOptions {
-        from_image: format!("{}:{}", image, tag),
-        ..Default::default()
+/// Parse a restart policy in the form `no`, `always`, `unless-stopped` or `on-failure[:max-retries]`.
+pub fn parse_restart_policy(input: &str) -> Result<RestartPolicy> {
+    let (name, retries) = match input.split_once(':') {
+        Some((name, retries)) => (name, Some(retries)),
+        None => (input, None),
     };
 
-    let mut stream = docker.create_image(Some(options), None, None);
-
-    while let Some(info) = stream.next().await {
-        let info = info?;
+    let name = match name {
+        "no" => RestartPolicyNameEnum::NO,
+        "always" => RestartPolicyNameEnum::ALWAYS,
+        "unless-stopped" => RestartPolicyNameEnum::UNLESS_STOPPED,
+        "on-failure" => RestartPolicyNameEnum::ON_FAILURE,
+        _ => bail!(Parse, "Invalid restart policy {}", input),
+    };
 
-        println!("{:?}", info);
-    }
+    let maximum_retry_count = retries
+        .map(|retries| {
+            ensure!(
+                name == RestartPolicyNameEnum::ON_FAILURE,
+                Parse,
+                "Maximum retry count can only be used with on-failure"
+            );
+
+            retries
+                .parse::<i64>()
+                .ok()
+                .filter(|retries| *retries >= 0)
+                .ok_or_else(|| Error::Parse(format!("Invalid maximum retry count {}", retries)))
+        })
+        .transpose()?;
 
-    Ok(())
+    Ok(RestartPolicy {
+        name: Some(name),
+        maximum_retry_count,
+    })
 }
 
-#[instrument]
-pub async fn start(
-    docker: &Docker,
-    containers: &[String],
-    attach: bool,
-    interactive: bool,
-) -> Result<()> {
+/// Parse a device mapping in the form `/dev/host[:/dev/container][:permissions]`.
+pub fn parse_device(input: &str) -> Result<DeviceMapping> {
+    let is_permissions = |s: &str| !s.is_empty() && s.chars().all(|c| matches!(c, 'r' | 'w' | 'm'));
+
+    let parts: Vec<&str> = input.split(':').collect();
+
+    let (host, container, permissions) = match parts.as_slice() {
+        [host] => (*host, *host, "rwm"),
+        [host, permissions] if is_permissions(permissions) => (*host, *host, *permissions),
+        [host, container] => (*host, *container, "rwm"),
+        [host, container, permissions] => (*host, *container, *permissions),
+        _ => bail!(Parse, "Invalid device {}", input),
+    };
+
     ensure!(
-        containers.len() == 1 || (!attach && !interactive),
-        "Can only attach to one container at a time"
+        host.starts_with('/') && container.starts_with('/'),
+        Parse,
+        "Invalid device {}: paths must be absolute",
+        input
     );
 
-    let options = StartContainerOptions::<&str> {
-        ..Default::default()
-    };
+    ensure!(
+        is_permissions(permissions),
+        Parse,
+        "Invalid device {}: invalid permissions {}",
+        input,
+        permissions
+    );
 
-    let mut attach_join: Vec<JoinHandle<Result<()>>> = Vec::new();
-    if attach || interactive {
-        for container in containers {
-            attach_join = attach_container(docker, container, interactive).await?;
-        }
-    }
+    Ok(DeviceMapping {
+        path_on_host: Some(host.to_string()),
+        path_in_container: Some(container.to_string()),
+        cgroup_permissions: Some(permissions.to_string()),
+    })
+}
 
-    let starts = containers.iter().map(|container| {
-        let docker = docker.clone();
-        let options = options.clone();
-        let container = container.clone();
+/// Parse an extra host entry in the form `host:ip` or `host=ip`, the IP can also be `host-gateway`.
+pub fn parse_extra_host(input: &str) -> Result<String> {
+    let (host, ip) = input
+        .split_once(['=', ':'])
+        .ok_or_else(|| Error::Parse(format!("Invalid extra host {}", input)))?;
 
-        tokio::spawn(async move {
-            let res = docker.start_container(&container, Some(options)).await;
-            (container, res)
-        })
-    });
+    ensure!(
+        !host.is_empty(),
+        Parse,
+        "Invalid extra host {}: empty host",
+        input
+    );
 
-    let err = join_all(starts)
-        .await
-        .iter()
-        .fold(false, |acc, join| match join {
-            Ok((container, Ok(()))) => {
-                println!("{container}");
-                acc
-            }
-            Ok((container, Err(err))) => {
-                error!(?container, ?err, "Failed to start container");
+    let ip = ip.trim_start_matches('[').trim_end_matches(']');
 
-                true
-            }
-            Err(e) => {
-                error!(?e, "Failed to start container");
+    ensure!(
+        ip == "host-gateway" || ip.parse::<IpAddr>().is_ok(),
+        Parse,
+        "Invalid extra host {}: invalid IP address {}",
+        input,
+        ip
+    );
 
-                true
-            }
-        });
+    Ok(format!("{}:{}", host, ip))
+}
+
+/// Parse a security option, inlining the content of a seccomp profile file.
+pub fn parse_security_opt(input: &str) -> Result<String> {
+    if input == "no-new-privileges" {
+        return Ok(input.to_string());
+    }
+
+    let (key, value) = input
+        .split_once(['=', ':'])
+        .ok_or_else(|| Error::Parse(format!("Invalid security option {}", input)))?;
 
-    ensure!(!err, "Failed to start containers");
+    match key {
+        "seccomp" if value != "unconfined" && value != "builtin" => {
+            let profile = fs::read_to_string(value).map_err(Error::io(format!(
+                "Failed to read seccomp profile {}",
+                value
+            )))?;
 
-    if attach || interactive {
-        for join in join_all(attach_join).await {
-            join??;
+            Ok(format!("seccomp={}", profile.trim()))
+        }
+        "seccomp" | "label" | "apparmor" | "no-new-privileges" | "systempaths" => {
+            Ok(format!("{}={}", key, value))
         }
+        _ => bail!(Parse, "Invalid security option {}", input),
     }
+}
 
-    Ok(())
+/// A container attached before being started, to stream all of its output.
+pub struct Attached {
+    /// The output of the container, ending when it exits
+    pub output: BoxStream<'static, Result<LogOutput>>,
+    /// The stdin of the container, when attached interactively
+    pub input: Option<Pin<Box<dyn AsyncWrite + Send>>>,
 }
 
-pub async fn stop(docker: &Docker, containers: &[String]) -> Result<()> {
-    let stops = containers.iter().map(|container| {
-        let docker = docker.clone();
-        let container = container.clone();
+/// Attach to the output of a container, and to its stdin with `interactive`.
+#[instrument(skip(docker))]
+pub async fn attach(docker: &Docker, container: &str, interactive: bool) -> Result<Attached> {
+    let options = AttachContainerOptions::<&str> {
+        stream: Some(true),
+        stdin: Some(interactive),
+        stdout: Some(true),
+        stderr: Some(true),
+        ..Default::default()
+    };
+
+    let AttachContainerResults { output, input } =
+        docker.attach_container(container, Some(options)).await?;
+
+    Ok(Attached {
+        output: output.map(|output| Ok(output?)).boxed(),
+        input: interactive.then_some(input),
+    })
+}
+
+/// When to pull the image before running a container.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum PullPolicy {
+    /// Always pull the image before running
+    Always,
+    /// Pull the image if it is not present locally
+    #[default]
+    Missing,
+    /// Never pull the image
+    Never,
+}
 
-        tokio::spawn(async move {
-            let res = docker.stop_container(&container, None).await;
-            (container, res)
+/// A container to run: it's created, once its image is available, then started and attached.
+///
+/// The image pulls are left to the caller, to display their progress.
+#[derive(Debug)]
+pub struct Run<'a> {
+    docker: &'a Docker,
+    options: Option<CreateContainerOptions<&'a str>>,
+    config: Config<String>,
+    reference: Reference,
+}
+
+impl<'a> Run<'a> {
+    pub fn new(
+        docker: &'a Docker,
+        options: Option<CreateContainerOptions<&'a str>>,
+        config: Config<String>,
+    ) -> Result<Self> {
+        let reference = config
+            .image
+            .as_deref()
+            .ok_or_else(|| Error::Invalid("Missing image to run".to_string()))?
+            .parse::<Reference>()?
+            .with_default_tag();
+
+        Ok(Self {
+            docker,
+            options,
+            config,
+            reference,
         })
-    });
+    }
 
-    let err = join_all(stops)
-        .await
-        .iter()
-        .fold(false, |err, join| match join {
-            Ok((container, Ok(()))) => {
-                println!("{container}");
+    /// The image of the container, with the default tag.
+    pub fn image(&self) -> String {
+        self.reference.to_string()
+    }
 
-                err
-            }
-            Ok((container, Err(err))) => {
-                error!(?container, ?err, "Failed to stop container");
+    /// Pull the image of the container, for the platform of the container if any.
+    pub async fn pull(&self) -> Pull<'a> {
+        let image = self.image();
+
+        let options = CreateImageOptions {
+            from_image: image.clone(),
+            platform: self
+                .options
+                .as_ref()
+                .and_then(|options| options.platform)
+                .unwrap_or_default()
+                .to_string(),
+            ..Default::default()
+        };
 
-                true
-            }
-            Err(e) => {
-                error!(?e, "Failed to stop container");
-                true
+        let progress = image_stream(self.docker, options, &self.reference.registry).await;
+
+        Pull { image, progress }
+    }
+
+    /// Create the container, returning its id or `None` if the image isn't present locally.
+    #[instrument(skip(self))]
+    pub async fn create(&self) -> Result<Option<String>> {
+        let container = match self
+            .docker
+            .create_container(self.options.clone(), self.config.clone())
+            .await
+        {
+            // A missing network or volume driver is also reported with a 404
+            Err(bollard::errors::Error::DockerResponseServerError {
+                status_code: 404,
+                ref message,
+            }) if message.starts_with("No such image") => return Ok(None),
+            res => res?,
+        };
+
+        if !container.warnings.is_empty() {
+            warn!("Warnings while creating the container");
+            for warning in container.warnings {
+                warn!(?warning);
             }
-        });
+        }
+
+        Ok(Some(container.id))
+    }
 
-    ensure!(!err, "Failed to stop containers");
+    /// Start the container, returning its output until it exits.
+    ///
+    /// The container is removed by the daemon once it exits if `auto_remove` is set in its
+    /// configuration.
+    #[instrument(skip(self))]
+    pub async fn start(&self, id: &str) -> Result<BoxStream<'a, Result<LogOutput>>> {
+        let Attached { output, .. } = attach(self.docker, id, false).await?;
 
-    Ok(())
+        self.docker
+            .start_container(id, None::<StartContainerOptions<&str>>)
+            .await?;
+
+        Ok(output)
+    }
 }
 
-pub async fn logs(
-    docker: &Docker,
-    container: &str,
-    follow: bool,
-    tail: Option<usize>,
-) -> Result<()> {
-    let options = LogsOptions::<String> {
-        follow,
-        stdout: true,
-        stderr: true,
-        tail: tail.map(|t| t.to_string()).unwrap_or_default(),
+/// An image being pulled, with the progress reported by the daemon.
+pub struct Pull<'a> {
+    /// The pulled image, without tag with `all_tags`.
+    pub image: String,
+    pub progress: BoxStream<'a, Result<CreateImageInfo>>,
+}
+
+/// Pull an image, returning the progress of the pull to be consumed by the caller.
+pub async fn pull<'a>(
+    docker: &'a Docker,
+    image: &str,
+    tag: Option<&str>,
+    all_tags: bool,
+    platform: Option<&str>,
+) -> Result<Pull<'a>> {
+    let mut reference: Reference = image.parse()?;
+
+    if let Some(tag) = tag {
+        ensure!(
+            reference.tag.is_none() && reference.digest.is_none(),
+            Parse,
+            "Image {} already has a tag or digest",
+            image
+        );
+
+        reference.tag = Some(tag.to_string());
+    }
+
+    let from_image = if all_tags {
+        ensure!(
+            reference.tag.is_none() && reference.digest.is_none(),
+            Parse,
+            "Tag or digest can't be used with --all-tags"
+        );
+
+        reference.name()
+    } else {
+        reference = reference.with_default_tag();
+
+        reference.to_string()
+    };
+
+    let options = CreateImageOptions {
+        from_image: from_image.clone(),
+        platform: platform.unwrap_or_default().to_string(),
         ..Default::default()
     };
 
-    let mut stream = docker.logs(container, Some(options));
+    let progress = image_stream(docker, options, &reference.registry).await;
 
-    while let Some(info) = stream.next().await {
-        let info = info?;
+    Ok(Pull {
+        image: from_image,
+        progress,
+    })
+}
 
-        print!("{}", info);
-    }
+/// Pull an image with the credentials for the registry.
+///
+/// The image is pulled anonymously if the credentials can't be read, since it may be public.
+async fn image_stream<'a>(
+    docker: &'a Docker,
+    options: CreateImageOptions<String>,
+    registry: &str,
+) -> BoxStream<'a, Result<CreateImageInfo>> {
+    let credentials = auth::registry_credentials(registry)
+        .await
+        .unwrap_or_else(|error| {
+            warn!(%error, registry, "Failed to get the credentials, pulling anonymously");
 
-    Ok(())
+            None
+        });
+
+    docker
+        .create_image(Some(options), None, credentials)
+        .map(|info| info.map_err(Into::into))
+        .boxed()
 }
 
-pub async fn rm(
-    docker: &Docker,
-    containers: &[String],
-    force: bool,
-    volumes: bool,
-    link: bool,
-) -> Result<()> {
-    let options = RemoveContainerOptions {
-        force,
-        v: volumes,
-        link,
-    };
+/// Store the credentials for a registry in the Docker CLI configuration.
+///
+/// The credentials aren't checked against the registry, the client can't authenticate with it.
+/// The credential helpers are run on a blocking thread.
+pub async fn login(server: Option<&str>, username: &str, password: &str) -> Result<()> {
+    let server = server.unwrap_or(DEFAULT_REGISTRY).to_string();
+    let username = username.to_string();
+    let password = password.to_string();
 
-    let removes = containers.iter().map(|container| {
-        let docker = docker.clone();
-        let container = container.clone();
+    tokio::task::spawn_blocking(move || {
+        let path = auth::config_path()?;
 
-        tokio::spawn(async move {
-            let res = docker.remove_container(&container, Some(options)).await;
-            (container, res)
-        })
-    });
+        let mut config = DockerConfig::load(&path)?;
 
-    let err = join_all(removes)
-        .await
-        .iter()
-        .fold(false, |err, join| match join {
-            Ok((container, Ok(()))) => {
-                println!("{container}");
+        config.store(&server, &username, &password)?;
+        config.save(&path)
+    })
+    .await?
+}
 
-                err
-            }
-            Ok((container, Err(err))) => {
-                error!(?container, ?err, "Failed to remove container");
+/// The outcome of a logout, with the normalized registry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Logout {
+    /// The credentials for the registry were removed
+    Removed(String),
+    /// There were no credentials for the registry
+    NotLoggedIn(String),
+}
 
-                true
-            }
-            Err(e) => {
-                error!(?e, "Failed to remove container");
+/// Remove the credentials for a registry from the Docker CLI configuration.
+///
+/// The credential helpers are run on a blocking thread.
+pub async fn logout(server: Option<&str>) -> Result<Logout> {
+    let server = server.unwrap_or(DEFAULT_REGISTRY).to_string();
 
-                true
-            }
-        });
+    tokio::task::spawn_blocking(move || {
+        let path = auth::config_path()?;
 
-    ensure!(!err, "Failed to remove containers");
+        let mut config = DockerConfig::load(&path)?;
 
-    Ok(())
+        if !config.erase(&server)? {
+            return Ok(Logout::NotLoggedIn(normalize_registry(&server).to_string()));
+        }
+
+        config.save(&path)?;
+
+        Ok(Logout::Removed(normalize_registry(&server).to_string()))
+    })
+    .await?
 }
 
-pub async fn rmi(docker: &Docker, images: &[String], force: bool) -> Result<()> {
-    let options = RemoveImageOptions {
-        force,
+/// Start the containers, with at most `parallel` requests in flight, returning the outcome of each
+/// one of them.
+#[instrument]
+pub async fn start(docker: &Docker, containers: &[String], parallel: NonZeroUsize) -> Batch<()> {
+    let options = StartContainerOptions::<&str> {
         ..Default::default()
     };
 
-    let removes = images.iter().map(|image| {
-        let docker = docker.clone();
-        let image = image.clone();
-
-        tokio::spawn(async move { docker.remove_image(&image, Some(options), None).await })
-    });
+    Batch::run(
+        docker,
+        "start containers",
+        containers,
+        parallel,
+        |docker, container| {
+            let options = options.clone();
+
+            async move { Ok(docker.start_container(&container, Some(options)).await?) }
+        },
+    )
+    .await
+}
 
-    let err = join_all(removes)
-        .await
-        .iter()
-        .fold(false, |err, join| match join {
-            Ok(Ok(responses)) => {
-                responses.iter().for_each(|resp| {
-                    if let Some(untaged) = &resp.untagged {
-                        println!("Untagged: {}", untaged);
+/// Seconds the daemon waits for a container to stop before killing it.
+const DEFAULT_STOP_TIME: i64 = 10;
+
+/// Stop the containers, with the outcome for each one of them.
+///
+/// A container is killed if it is still running `time` seconds after being asked to stop (10 by
+/// default, or forever if negative). The stop endpoint can't be given a signal through bollard,
+/// so a custom `signal` is sent with a kill request and the grace period is enforced here.
+///
+/// Like the docker CLI, the requests wait `time` seconds longer than the client timeout.
+pub async fn stop(
+    docker: &Docker,
+    containers: &[String],
+    time: Option<i64>,
+    signal: Option<&str>,
+    parallel: NonZeroUsize,
+) -> Batch<()> {
+    let signal = signal.map(str::to_string);
+
+    Batch::run(
+        docker,
+        "stop containers",
+        containers,
+        parallel,
+        |docker, container| {
+            let signal = signal.clone();
+
+            async move {
+                match signal {
+                    Some(signal) => {
+                        let time = time.unwrap_or(DEFAULT_STOP_TIME);
+                        let docker = with_stop_timeout(docker, time);
+
+                        stop_with_signal(&docker, &container, time, signal).await
                     }
-
-                    if let Some(deleted) = &resp.deleted {
-                        println!("Deleted: {}", deleted);
+                    None => {
+                        let docker = match time {
+                            Some(time) => with_stop_timeout(docker, time),
+                            None => docker,
+                        };
+                        let options = time.map(|t| StopContainerOptions { t });
+
+                        Ok(docker.stop_container(&container, options).await?)
                     }
-                });
-
-                err
+                }
             }
-            Ok(Err(err)) => {
-                error!(?err, "Failed to remove image");
+        },
+    )
+    .await
+}
 
-                true
-            }
-            Err(e) => {
-                error!(?e, "Failed to remove image");
+/// Extends the timeout of the client by the seconds given to a container to stop, without
+/// timeout if it is waited forever.
+fn with_stop_timeout(docker: Docker, time: i64) -> Docker {
+    let timeout = match u64::try_from(time) {
+        Ok(time) => docker.timeout().saturating_add(Duration::from_secs(time)),
+        Err(_) => Duration::MAX,
+    };
 
-                true
-            }
-        });
+    docker.with_timeout(timeout)
+}
+
+/// Sends the signal to the container and kills it if it is still running after `time` seconds.
+async fn stop_with_signal(
+    docker: &Docker,
+    container: &str,
+    time: i64,
+    signal: String,
+) -> Result<()> {
+    match docker
+        .kill_container(container, Some(KillContainerOptions { signal }))
+        .await
+        .map_err(Error::from)
+    {
+        // The container isn't running
+        Err(Error::Conflict { .. }) => return Ok(()),
+        result => result?,
+    }
 
-    ensure!(!err, "Failed to remove images");
+    let options = WaitContainerOptions {
+        condition: "not-running".to_string(),
+    };
+    let mut wait = docker.wait_container(container, Some(options));
+
+    let next = match u64::try_from(time) {
+        Ok(time) => tokio::time::timeout(Duration::from_secs(time), wait.next())
+            .await
+            .ok()
+            .flatten(),
+        Err(_) => wait.next().await,
+    };
+
+    // The exit code doesn't matter, only that the container stopped, the client returns an error
+    // for the non-zero ones
+    let stopped = match next {
+        Some(Ok(_)) | Some(Err(bollard::errors::Error::DockerContainerWaitError { .. })) => true,
+        Some(Err(error)) => return Err(error.into()),
+        None => false,
+    };
+
+    if !stopped {
+        let options = KillContainerOptions {
+            signal: "KILL".to_string(),
+        };
+
+        match docker
+            .kill_container(container, Some(options))
+            .await
+            .map_err(Error::from)
+        {
+            // The container stopped in the meantime
+            Err(Error::Conflict { .. }) => {}
+            result => result?,
+        }
+    }
 
     Ok(())
 }
 
-#[instrument(skip(docker))]
-pub async fn events(docker: &Docker, filter: &[String]) -> Result<()> {
-    let filters: HashMap<&str, Vec<&str>> =
-        filter
-            .iter()
-            .try_fold(HashMap::new(), |mut acc, filter| -> Result<_> {
-                let (filter, value) = parse_filter(filter)?;
+/// Kill the containers with the signal, with the outcome for each one of them.
+pub async fn kill(
+    docker: &Docker,
+    containers: &[String],
+    signal: &str,
+    parallel: NonZeroUsize,
+) -> Batch<()> {
+    Batch::run(
+        docker,
+        "kill containers",
+        containers,
+        parallel,
+        |docker, container| {
+            let options = KillContainerOptions {
+                signal: signal.to_string(),
+            };
 
-                acc.entry(filter).or_insert_with(Vec::new).push(value);
+            async move { Ok(docker.kill_container(&container, Some(options)).await?) }
+        },
+    )
+    .await
+}
 
-                Ok(acc)
-            })?;
+/// Remove the containers, with the outcome for each one of them.
+pub async fn rm(
+    docker: &Docker,
+    containers: &[String],
+    force: bool,
+    volumes: bool,
+    link: bool,
+    parallel: NonZeroUsize,
+) -> Batch<()> {
+    let options = RemoveContainerOptions {
+        force,
+        v: volumes,
+        link,
+    };
 
-    let options = EventsOptions {
-        filters,
+    Batch::run(
+        docker,
+        "remove containers",
+        containers,
+        parallel,
+        |docker, container| async move {
+            Ok(docker.remove_container(&container, Some(options)).await?)
+        },
+    )
+    .await
+}
+
+/// Remove the images, with the untagged and deleted references for each one of them.
+pub async fn rmi(
+    docker: &Docker,
+    images: &[String],
+    force: bool,
+    parallel: NonZeroUsize,
+) -> Batch<Vec<ImageDeleteResponseItem>> {
+    let options = RemoveImageOptions {
+        force,
         ..Default::default()
     };
 
-    let mut stream = docker.events(Some(options));
-
-    while let Some(event) = stream.next().await {
-        let event = event?;
-
-        let time = Local::now();
-
-        let typ = event
-            .typ
-            .map(|t| t.to_string())
-            .unwrap_or_else(|| "".to_string());
-
-        let action = event.action.unwrap_or_default();
-
-        let actor_id = event
-            .actor
-            .as_ref()
-            .and_then(|actor| actor.id.as_deref())
-            .unwrap_or("");
-
-        let actor_attributes = event
-            .actor
-            .as_ref()
-            .and_then(|actor| actor.attributes.as_ref())
-            .map(|attr| {
-                attr.iter()
-                    .map(|(k, v)| format!("{}={}", k, v))
-                    .collect::<Vec<_>>()
-                    .join(", ")
-            })
-            .unwrap_or_else(|| "".to_string());
-
-        println!(
-            "{} {} {} {} ({})",
-            time.to_rfc3339(),
-            typ,
-            action,
-            actor_id,
-            actor_attributes
-        );
-    }
+    Batch::run(
+        docker,
+        "remove images",
+        images,
+        parallel,
+        |docker, image| async move {
+            let image = if is_image_id(&image) {
+                image
+            } else {
+                image.parse::<Reference>()?.with_default_tag().to_string()
+            };
 
-    Ok(())
+            Ok(docker.remove_image(&image, Some(options), None).await?)
+        },
+    )
+    .await
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use bollard::container::LogsOptions;
 
     #[macro_export]
     macro_rules! docker_test {
@@ -491,12 +994,27 @@ mod test {
             let docker: Docker = $mock;
 
             #[cfg(not(feature = "mock"))]
-            let docker: Docker = $crate::connect_to_docker().unwrap();
+            let docker: Docker = $crate::connect_to_docker(None, None, None).await.unwrap();
 
             docker
         }};
     }
 
+    #[test]
+    fn test_resolve_host() {
+        let env_host = Some("tcp://build:2375");
+
+        // The default context uses DOCKER_HOST like when no context is given
+        assert_eq!(
+            resolve_host(None, Some(context::DEFAULT_CONTEXT), env_host).unwrap(),
+            Host::Http("build:2375".to_string())
+        );
+        assert_eq!(
+            resolve_host(Some("tcp://remote:2375"), Some("default"), env_host).unwrap(),
+            Host::Http("remote:2375".to_string())
+        );
+    }
+
     #[test]
     fn test_get_port_binding() {
         let input = ["80", "443:8080", "127.0.0.1:80:8080"];
@@ -504,9 +1022,9 @@ mod test {
         let binginds = get_port_bindings(&input).unwrap();
 
         let expected = [
-            ("80".to_string(), Some(vec![])),
+            ("80/tcp".to_string(), Some(vec![])),
             (
-                "8080".to_string(),
+                "8080/tcp".to_string(),
                 Some(vec![
                     PortBinding {
                         host_ip: None,
@@ -531,25 +1049,264 @@ mod test {
         let binginds = get_port_bindings(&input).unwrap();
 
         let expected = [(
-            "8080".to_string(),
+            "8080/tcp".to_string(),
             Some(vec![PortBinding {
-                host_ip: Some("[::]".to_string()),
+                host_ip: Some("::".to_string()),
                 host_port: Some("443".to_string()),
             }]),
         )];
 
-        assert_eq!(binginds, HashMap::from(expected));
-    }
+        assert_eq!(binginds, HashMap::from(expected));
+
+        let input = ["[::1]::53/udp"];
+
+        let binginds = get_port_bindings(&input).unwrap();
+
+        let expected = [(
+            "53/udp".to_string(),
+            Some(vec![PortBinding {
+                host_ip: Some("::1".to_string()),
+                host_port: None,
+            }]),
+        )];
+
+        assert_eq!(binginds, HashMap::from(expected));
+    }
+
+    #[test]
+    fn test_get_port_binding_range() {
+        let input = ["8000-8002:9000-9002", "53:53/udp", "7000-7001"];
+
+        let binginds = get_port_bindings(&input).unwrap();
+
+        let binding = |port: &str| {
+            Some(vec![PortBinding {
+                host_ip: None,
+                host_port: Some(port.to_string()),
+            }])
+        };
+
+        let expected = [
+            ("9000/tcp".to_string(), binding("8000")),
+            ("9001/tcp".to_string(), binding("8001")),
+            ("9002/tcp".to_string(), binding("8002")),
+            ("53/udp".to_string(), binding("53")),
+            ("7000/tcp".to_string(), Some(vec![])),
+            ("7001/tcp".to_string(), Some(vec![])),
+        ];
+
+        assert_eq!(binginds, HashMap::from(expected));
+
+        let input = ["8000-8010:80"];
+
+        let binginds = get_port_bindings(&input).unwrap();
+
+        let expected = [("80/tcp".to_string(), binding("8000-8010"))];
+
+        assert_eq!(binginds, HashMap::from(expected));
+    }
+
+    #[test]
+    fn test_get_port_binding_invalid() {
+        let cases = [
+            "8000-8010:9000-9005",
+            "80:80/icmp",
+            "9000-8000",
+            "http",
+            "[::1:80:80",
+            "[not-an-ip]:80:80",
+            "::1:80:80",
+            "70000:80",
+        ];
+
+        for input in cases {
+            assert!(
+                matches!(get_port_bindings(&[input]), Err(Error::Parse(_))),
+                "{}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_get_exposed_ports() {
+        let input = ["80", "53/udp", "8000-8001"];
+
+        let exposed = get_exposed_ports(&input).unwrap();
+
+        let expected = ["80/tcp", "53/udp", "8000/tcp", "8001/tcp"]
+            .map(|port| (port.to_string(), HashMap::new()));
+
+        assert_eq!(exposed, HashMap::from(expected));
+
+        assert!(get_exposed_ports(&["80/foo"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_filter() {
+        let input = "label=foo=bar";
+
+        let filter = parse_filter(input).unwrap();
+
+        let expected = ("label", "foo=bar");
+
+        assert_eq!(filter, expected);
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        let cases = [
+            ("0", Duration::ZERO),
+            ("30s", Duration::from_secs(30)),
+            ("1m30s", Duration::from_secs(90)),
+            ("1.5h", Duration::from_secs(5400)),
+            ("500ms", Duration::from_millis(500)),
+        ];
+
+        for (input, expected) in cases {
+            assert_eq!(parse_duration(input).unwrap(), expected, "{}", input);
+        }
+
+        for input in ["", "10", "1x", "s", "1..5s", "99999999999999999999h"] {
+            assert!(parse_duration(input).is_err(), "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_parse_device() {
+        let cases = [
+            ("/dev/fuse", "/dev/fuse", "/dev/fuse", "rwm"),
+            ("/dev/sda:r", "/dev/sda", "/dev/sda", "r"),
+            ("/dev/sda:/dev/xvdc", "/dev/sda", "/dev/xvdc", "rwm"),
+            ("/dev/sda:/dev/xvdc:rw", "/dev/sda", "/dev/xvdc", "rw"),
+        ];
+
+        for (input, host, container, permissions) in cases {
+            let expected = DeviceMapping {
+                path_on_host: Some(host.to_string()),
+                path_in_container: Some(container.to_string()),
+                cgroup_permissions: Some(permissions.to_string()),
+            };
+
+            assert_eq!(parse_device(input).unwrap(), expected, "{}", input);
+        }
+
+        for input in [
+            "sda",
+            "/dev/sda:xvdc",
+            "/dev/sda:/dev/xvdc:rx",
+            "/a:/b:rw:m",
+        ] {
+            assert!(parse_device(input).is_err(), "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_parse_extra_host() {
+        let cases = [
+            ("db:10.0.0.2", "db:10.0.0.2"),
+            ("db=10.0.0.2", "db:10.0.0.2"),
+            ("ipv6:::1", "ipv6:::1"),
+            ("ipv6=[2001:db8::1]", "ipv6:2001:db8::1"),
+            (
+                "host.docker.internal:host-gateway",
+                "host.docker.internal:host-gateway",
+            ),
+        ];
+
+        for (input, expected) in cases {
+            assert_eq!(parse_extra_host(input).unwrap(), expected, "{}", input);
+        }
+
+        for input in ["db", "db:not-an-ip", ":10.0.0.2"] {
+            assert!(parse_extra_host(input).is_err(), "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_parse_security_opt() {
+        let cases = [
+            ("no-new-privileges", "no-new-privileges"),
+            ("no-new-privileges:true", "no-new-privileges=true"),
+            ("seccomp=unconfined", "seccomp=unconfined"),
+            ("label=disable", "label=disable"),
+        ];
+
+        for (input, expected) in cases {
+            assert_eq!(parse_security_opt(input).unwrap(), expected, "{}", input);
+        }
+
+        let profile = std::env::temp_dir().join("dockrs-test-seccomp.json");
+        fs::write(&profile, "{\"defaultAction\": \"SCMP_ACT_ERRNO\"}\n").unwrap();
+
+        let opt = parse_security_opt(&format!("seccomp={}", profile.display())).unwrap();
+
+        fs::remove_file(&profile).unwrap();
+
+        assert_eq!(opt, "seccomp={\"defaultAction\": \"SCMP_ACT_ERRNO\"}");
+
+        for input in ["foo=bar", "apparmor", "seccomp=/does/not/exist.json"] {
+            assert!(parse_security_opt(input).is_err(), "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_parse_timestamp() {
+        use chrono::TimeZone;
+
+        let now = DateTime::parse_from_rfc3339("2023-05-01T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+
+        let cases = [
+            ("2023-05-01T10:00:00Z", 1682935200, 0),
+            ("2023-05-01T12:00:00+02:00", 1682935200, 0),
+            ("1682935200", 1682935200, 0),
+            ("1682935200.75", 1682935200, 750_000_000),
+            ("10m", 1682941800, 0),
+            ("1h30m", 1682937000, 0),
+            ("1.5s", 1682942398, 500_000_000),
+        ];
+
+        for (input, secs, nanos) in cases {
+            let time = parse_timestamp(input, now).unwrap();
+
+            assert_eq!(time.timestamp(), secs, "{}", input);
+            assert_eq!(time.timestamp_subsec_nanos(), nanos, "{}", input);
+        }
+
+        let local = Local.with_ymd_and_hms(2023, 5, 1, 0, 0, 0).unwrap();
+
+        assert_eq!(parse_timestamp("2023-05-01", now).unwrap(), local);
+
+        for input in ["", "yesterday", "2023-13-01", "1.2.3", "2000000000000h"] {
+            assert!(parse_timestamp(input, now).is_err(), "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_parse_restart_policy() {
+        let policy = parse_restart_policy("on-failure:3").unwrap();
+
+        let expected = RestartPolicy {
+            name: Some(RestartPolicyNameEnum::ON_FAILURE),
+            maximum_retry_count: Some(3),
+        };
+
+        assert_eq!(policy, expected);
 
-    #[test]
-    fn test_parse_filter() {
-        let input = "label=foo=bar";
+        let policy = parse_restart_policy("unless-stopped").unwrap();
 
-        let filter = parse_filter(input).unwrap();
+        let expected = RestartPolicy {
+            name: Some(RestartPolicyNameEnum::UNLESS_STOPPED),
+            maximum_retry_count: None,
+        };
 
-        let expected = ("label", "foo=bar");
+        assert_eq!(policy, expected);
 
-        assert_eq!(filter, expected);
+        for input in ["sometimes", "always:3", "on-failure:-1", "on-failure:"] {
+            assert!(parse_restart_policy(input).is_err(), "{}", input);
+        }
     }
 
     #[tokio::test]
@@ -576,21 +1333,113 @@ mod test {
             mock.expect_attach_container()
                 .return_once(|_, _| Ok(attach_container));
             mock.expect_start_container().return_once(|_, _| Ok(()));
-            mock.expect_remove_container().return_once(|_, _| Ok(()));
 
             mock
         });
 
-        let options = None;
         let config = Config {
-            image: Some("hello-world"),
+            image: Some("hello-world".to_string()),
+            host_config: Some(bollard::models::HostConfig {
+                auto_remove: Some(true),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let run = Run::new(&docker, None, config).unwrap();
+
+        let id = run.create().await.unwrap();
+
+        assert!(id.is_some(), "the image is missing");
+
+        let output = run.start(&id.unwrap()).await.unwrap();
+        let result = output.collect::<Vec<_>>().await;
+
+        assert!(
+            result.iter().all(Result::is_ok),
+            "run failed with {:?}",
+            result
+        );
+    }
+
+    #[tokio::test]
+    async fn test_run_missing_image() {
+        let docker = docker_test!({
+            use bollard::errors::Error;
+            use mock::MockDocker;
+
+            let mut mock = MockDocker::new();
+
+            mock.expect_create_container().times(1).return_once(|_, _| {
+                Err(Error::DockerResponseServerError {
+                    status_code: 404,
+                    message: "No such image: dockrs/missing:latest".to_string(),
+                })
+            });
+            mock.expect_create_image()
+                .withf(|options, _, _| {
+                    options.as_ref().map(|options| options.from_image.as_str())
+                        == Some("docker.io/dockrs/missing:latest")
+                })
+                .times(1)
+                .return_once(|_, _, _| Box::pin(futures::stream::empty()));
+
+            mock
+        });
+
+        let config = Config {
+            image: Some("dockrs/missing".to_string()),
+            ..Default::default()
+        };
+
+        let run = Run::new(&docker, None, config).unwrap();
+
+        assert_eq!(run.image(), "docker.io/dockrs/missing:latest");
+        assert!(matches!(run.create().await, Ok(None)));
+
+        let pull = run.pull().await;
+
+        assert_eq!(pull.image, "docker.io/dockrs/missing:latest");
+
+        if cfg!(feature = "mock") {
+            assert_eq!(pull.progress.count().await, 0);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_run_missing_network() {
+        let docker = docker_test!({
+            use bollard::errors::Error;
+            use mock::MockDocker;
+
+            let mut mock = MockDocker::new();
+
+            mock.expect_create_container().times(1).return_once(|_, _| {
+                Err(Error::DockerResponseServerError {
+                    status_code: 404,
+                    message: "network dockrs-missing not found".to_string(),
+                })
+            });
+
+            mock
+        });
+
+        let config = Config {
+            image: Some("hello-world".to_string()),
+            host_config: Some(bollard::models::HostConfig {
+                network_mode: Some("dockrs-missing".to_string()),
+                ..Default::default()
+            }),
             ..Default::default()
         };
-        let rm = true;
 
-        let result = run(&docker, options, config, rm).await;
+        let result = Run::new(&docker, None, config).unwrap().create().await;
 
-        assert!(result.is_ok(), "run failed with {:?}", result);
+        assert!(
+            matches!(result, Err(Error::NotFound { .. })),
+            "run didn't fail with the missing network: {:?}",
+            result
+        );
     }
 
     #[tokio::test]
@@ -607,11 +1456,46 @@ mod test {
         });
 
         let image = "hello-world";
-        let tag = "latest";
+        let tag = Some("latest");
+
+        let result = pull(&docker, image, tag, false, None).await;
+
+        assert!(result.is_ok(), "pull failed");
+
+        let pull = result.unwrap();
+
+        assert_eq!(pull.image, "docker.io/library/hello-world:latest");
+        assert_eq!(pull.progress.count().await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_pull_all_tags() {
+        let docker = docker_test!({
+            use mock::MockDocker;
+
+            let mut mock = MockDocker::new();
+
+            mock.expect_create_image()
+                .withf(|options, _, _| {
+                    options.as_ref().is_some_and(|options| {
+                        options.from_image == "docker.io/library/alpine"
+                            && options.tag.is_empty()
+                            && options.platform == "linux/arm64"
+                    })
+                })
+                .return_once(|_, _, _| Box::pin(futures::stream::empty()));
+
+            mock
+        });
+
+        let result = pull(&docker, "alpine", None, true, Some("linux/arm64")).await;
+
+        assert!(result.is_ok(), "pull failed");
+        assert_eq!(result.unwrap().image, "docker.io/library/alpine");
 
-        let result = pull(&docker, image, tag).await;
+        let result = pull(&docker, "alpine:3.18", None, true, None).await;
 
-        assert!(result.is_ok(), "pull failed with {:?}", result);
+        assert!(result.is_err(), "pull with tag and all tags succeeded");
     }
 
     #[tokio::test]
@@ -633,10 +1517,10 @@ mod test {
         });
 
         let containers = vec!["test".to_string()];
-        let attach = false;
-        let interactive = false;
 
-        let result = start(&docker, &containers, attach, interactive).await;
+        let result = start(&docker, &containers, DEFAULT_PARALLEL)
+            .await
+            .summary();
 
         assert!(result.is_ok(), "start failed with {:?}", result);
     }
@@ -651,7 +1535,9 @@ mod test {
             mock.expect_clone().return_once(|| {
                 let mut mock = MockDocker::new();
 
-                mock.expect_stop_container().return_once(|_, _| Ok(()));
+                mock.expect_stop_container()
+                    .withf(|_, options| options.is_none())
+                    .return_once(|_, _| Ok(()));
 
                 mock
             });
@@ -661,43 +1547,82 @@ mod test {
 
         let containers = vec!["test".to_string()];
 
-        let result = stop(&docker, &containers).await;
+        let result = stop(&docker, &containers, None, None, DEFAULT_PARALLEL)
+            .await
+            .summary();
 
         assert!(result.is_ok(), "stop failed with {:?}", result);
     }
 
     #[tokio::test]
-    async fn test_logs() {
+    async fn test_stop_time() {
         let docker = docker_test!({
             use mock::MockDocker;
 
             let mut mock = MockDocker::new();
 
-            mock.expect_logs()
-                .return_once(|_, _| Box::pin(futures::stream::empty()));
+            mock.expect_clone().return_once(|| {
+                let mut mock = MockDocker::new();
+
+                mock.expect_timeout().return_const(Duration::from_secs(120));
+                mock.expect_with_timeout()
+                    .withf(|timeout| *timeout == Duration::from_secs(180))
+                    .return_once(|_| {
+                        let mut mock = MockDocker::new();
+
+                        mock.expect_stop_container()
+                            .withf(|_, options| {
+                                options.as_ref().map(|options| options.t) == Some(60)
+                            })
+                            .return_once(|_, _| Ok(()));
+
+                        mock
+                    });
+
+                mock
+            });
 
             mock
         });
 
-        let container = "test";
-        let follow = false;
-        let tail = None;
+        let containers = vec!["test".to_string()];
 
-        let result = logs(&docker, container, follow, tail).await;
+        let result = stop(&docker, &containers, Some(60), None, DEFAULT_PARALLEL)
+            .await
+            .summary();
 
-        assert!(result.is_ok(), "logs failed with {:?}", result);
+        assert!(result.is_ok(), "stop failed with {:?}", result);
     }
 
     #[tokio::test]
-    async fn test_remove() {
+    async fn test_stop_signal() {
         let docker = docker_test!({
+            use bollard::models::ContainerWaitResponse;
             use mock::MockDocker;
 
             let mut mock = MockDocker::new();
 
-            mock.expect_clone().returning(|| {
+            mock.expect_clone().return_once(|| {
                 let mut mock = MockDocker::new();
-                mock.expect_remove_container().return_once(|_, _| Ok(()));
+
+                mock.expect_timeout().return_const(Duration::from_secs(120));
+                mock.expect_with_timeout()
+                    .withf(|timeout| *timeout == Duration::from_secs(121))
+                    .return_once(|_| {
+                        let mut mock = MockDocker::new();
+
+                        mock.expect_kill_container()
+                            .withf(|_, options| options.as_ref().unwrap().signal == "SIGINT")
+                            .times(1)
+                            .return_once(|_, _| Ok(()));
+                        mock.expect_wait_container().return_once(|_, _| {
+                            Box::pin(futures::stream::once(async {
+                                Ok(ContainerWaitResponse::default())
+                            }))
+                        });
+
+                        mock
+                    });
 
                 mock
             });
@@ -707,14 +1632,23 @@ mod test {
 
         let containers = vec!["test".to_string()];
 
-        let result = rm(&docker, &containers, true, true, true).await;
+        let result = stop(
+            &docker,
+            &containers,
+            Some(1),
+            Some("SIGINT"),
+            DEFAULT_PARALLEL,
+        )
+        .await
+        .summary();
 
-        assert!(result.is_ok(), "remove failed with {:?}", result);
+        assert!(result.is_ok(), "stop failed with {:?}", result);
     }
 
     #[tokio::test]
-    async fn test_rmi() {
+    async fn test_stop_signal_wait_error() {
         let docker = docker_test!({
+            use bollard::errors::Error;
             use mock::MockDocker;
 
             let mut mock = MockDocker::new();
@@ -722,7 +1656,62 @@ mod test {
             mock.expect_clone().return_once(|| {
                 let mut mock = MockDocker::new();
 
-                mock.expect_remove_image().return_once(|_, _, _| Ok(vec![]));
+                mock.expect_timeout().return_const(Duration::from_secs(120));
+                mock.expect_with_timeout().return_once(|_| {
+                    let mut mock = MockDocker::new();
+
+                    // The container isn't killed if waiting for it failed
+                    mock.expect_kill_container()
+                        .times(1)
+                        .return_once(|_, _| Ok(()));
+                    mock.expect_wait_container().return_once(|_, _| {
+                        Box::pin(futures::stream::once(async {
+                            Err(Error::DockerResponseServerError {
+                                status_code: 404,
+                                message: "No such container: test".to_string(),
+                            })
+                        }))
+                    });
+
+                    mock
+                });
+
+                mock
+            });
+
+            mock
+        });
+
+        if cfg!(feature = "mock") {
+            let containers = vec!["test".to_string()];
+
+            let result = stop(
+                &docker,
+                &containers,
+                Some(1),
+                Some("SIGINT"),
+                DEFAULT_PARALLEL,
+            )
+            .await
+            .summary();
+
+            assert!(result.is_err(), "stop succeeded");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_kill() {
+        let docker = docker_test!({
+            use mock::MockDocker;
+
+            let mut mock = MockDocker::new();
+
+            mock.expect_clone().return_once(|| {
+                let mut mock = MockDocker::new();
+
+                mock.expect_kill_container()
+                    .withf(|_, options| options.as_ref().unwrap().signal == "KILL")
+                    .return_once(|_, _| Ok(()));
 
                 mock
             });
@@ -730,26 +1719,420 @@ mod test {
             mock
         });
 
-        let result = rmi(&docker, &["test".to_string()], true).await;
+        let containers = vec!["test".to_string()];
+
+        let result = kill(&docker, &containers, "KILL", DEFAULT_PARALLEL)
+            .await
+            .summary();
+
+        assert!(result.is_ok(), "kill failed with {:?}", result);
+    }
+
+    #[tokio::test]
+    async fn test_logs() {
+        let docker = docker_test!({
+            use mock::MockDocker;
+
+            let mut mock = MockDocker::new();
+
+            mock.expect_logs()
+                .return_once(|_, _| Box::pin(futures::stream::empty()));
+
+            mock
+        });
+
+        let containers = ["test".to_string()];
+        let options = LogsOptions::<String> {
+            stdout: true,
+            stderr: true,
+            ..Default::default()
+        };
+
+        let events = logs(&docker, &containers, &[], options, true)
+            .await
+            .unwrap()
+            .collect::<Vec<_>>()
+            .await;
+
+        assert!(
+            events.iter().all(Result::is_ok),
+            "logs failed with {:?}",
+            events
+        );
+    }
+
+    #[tokio::test]
+    async fn test_logs_partial_line() {
+        let docker = docker_test!({
+            use mock::MockDocker;
+
+            let mut mock = MockDocker::new();
+
+            mock.expect_logs().return_once(|_, _| {
+                Box::pin(
+                    futures::stream::once(async {
+                        Ok(LogOutput::StdOut {
+                            message: "password: ".into(),
+                        })
+                    })
+                    .chain(futures::stream::pending()),
+                )
+            });
+
+            mock
+        });
+
+        let containers = ["test".to_string()];
+        let options = LogsOptions::<String> {
+            follow: true,
+            stdout: true,
+            stderr: true,
+            ..Default::default()
+        };
+
+        let mut events = logs(&docker, &containers, &[], options, false)
+            .await
+            .unwrap();
+
+        if cfg!(feature = "mock") {
+            // The frame isn't held back until a line end
+            let event = tokio::time::timeout(Duration::from_millis(100), events.next())
+                .await
+                .expect("the partial line was held back")
+                .unwrap()
+                .unwrap();
+
+            assert!(
+                matches!(
+                    event,
+                    LogEvent::Output {
+                        container: None,
+                        output: LogOutput::StdOut { ref message },
+                    } if message == "password: "
+                ),
+                "{:?}",
+                event
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_logs_filter() {
+        let docker = docker_test!({
+            use bollard::service::ContainerSummary;
+            use mock::MockDocker;
+
+            let mut mock = MockDocker::new();
+
+            mock.expect_list_containers()
+                .withf(|options| {
+                    let options = options.as_ref().unwrap();
+
+                    options.all && options.filters.get("label") == Some(&vec!["app=shop"])
+                })
+                .return_once(|_| {
+                    Ok(vec![ContainerSummary {
+                        names: Some(vec!["/worker".to_string()]),
+                        ..Default::default()
+                    }])
+                });
+
+            mock.expect_logs()
+                .times(2)
+                .returning(|_, _| Box::pin(futures::stream::empty()));
+
+            mock
+        });
+
+        let containers = ["web".to_string(), "worker".to_string()];
+        let filter = ["label=app=shop".to_string()];
+        let options = LogsOptions::<String> {
+            stdout: true,
+            stderr: true,
+            ..Default::default()
+        };
+
+        let events = logs(&docker, &containers, &filter, options, true)
+            .await
+            .unwrap()
+            .collect::<Vec<_>>()
+            .await;
+
+        if cfg!(feature = "mock") {
+            let attached = events.into_iter().map(Result::unwrap).collect::<Vec<_>>();
+
+            assert!(
+                matches!(
+                    attached.as_slice(),
+                    [LogEvent::Attached(web), LogEvent::Attached(worker)]
+                        if web == "web" && worker == "worker"
+                ),
+                "{:?}",
+                attached
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_logs_follow() {
+        let docker = docker_test!({
+            use bollard::{errors::Error, service::ContainerSummary};
+            use mock::MockDocker;
+
+            let mut mock = MockDocker::new();
+
+            mock.expect_list_containers().return_once(|_| {
+                Ok(vec![ContainerSummary {
+                    id: Some("abc".to_string()),
+                    names: Some(vec!["/worker".to_string()]),
+                    ..Default::default()
+                }])
+            });
+            mock.expect_events()
+                .withf(|options| {
+                    let filters = &options.as_ref().unwrap().filters;
+
+                    filters.get("container") == Some(&vec!["web".to_string()])
+                        && !filters.contains_key("label")
+                })
+                .return_once(|_| Box::pin(futures::stream::pending()));
+            mock.expect_events()
+                .withf(|options| {
+                    let filters = &options.as_ref().unwrap().filters;
+
+                    filters.get("label") == Some(&vec!["app=shop".to_string()])
+                        && !filters.contains_key("container")
+                })
+                .return_once(|_| Box::pin(futures::stream::pending()));
+            mock.expect_logs()
+                .withf(|name, _| name == "web")
+                .return_once(|_, _| {
+                    Box::pin(futures::stream::once(async {
+                        Err(Error::DockerResponseServerError {
+                            status_code: 404,
+                            message: "No such container: web".to_string(),
+                        })
+                    }))
+                });
+            mock.expect_logs()
+                .withf(|name, _| name == "worker")
+                .return_once(|_, _| {
+                    Box::pin(futures::stream::once(async {
+                        Ok(LogOutput::StdOut {
+                            message: "ready\n".into(),
+                        })
+                    }))
+                });
+
+            mock
+        });
+
+        let containers = ["web".to_string()];
+        let filter = ["label=app=shop".to_string()];
+        let options = LogsOptions::<String> {
+            follow: true,
+            stdout: true,
+            stderr: true,
+            ..Default::default()
+        };
 
-        assert!(result.is_ok(), "ps failed with {:?}", result);
+        let events = logs(&docker, &containers, &filter, options, true)
+            .await
+            .unwrap()
+            .take(4)
+            .collect::<Vec<_>>();
+
+        if cfg!(feature = "mock") {
+            let events = events
+                .await
+                .into_iter()
+                .map(Result::unwrap)
+                .collect::<Vec<_>>();
+
+            assert!(
+                matches!(
+                    &events[..2],
+                    [LogEvent::Attached(web), LogEvent::Attached(worker)]
+                        if web == "web" && worker == "worker"
+                ),
+                "{:?}",
+                events
+            );
+
+            // The error of web is returned without stopping the logs of worker
+            assert!(
+                events[2..].iter().any(|event| matches!(
+                    event,
+                    LogEvent::Error {
+                        container,
+                        error: crate::Error::NotFound { .. },
+                    } if container == "web"
+                )),
+                "{:?}",
+                events
+            );
+            assert!(
+                events[2..].iter().any(|event| matches!(
+                    event,
+                    LogEvent::Output {
+                        container: Some(container),
+                        output: LogOutput::StdOut { message },
+                    } if container == "worker" && message == "ready\n"
+                )),
+                "{:?}",
+                events
+            );
+        }
     }
 
     #[tokio::test]
-    async fn test_events() {
+    async fn test_logs_follow_filter() {
         let docker = docker_test!({
+            use bollard::{
+                models::{EventActor, EventMessage},
+                service::ContainerSummary,
+            };
             use mock::MockDocker;
 
             let mut mock = MockDocker::new();
 
+            mock.expect_list_containers()
+                .withf(|options| !options.as_ref().unwrap().filters.contains_key("id"))
+                .return_once(|_| Ok(Vec::new()));
+            mock.expect_list_containers()
+                .withf(|options| options.as_ref().unwrap().filters.get("id") == Some(&vec!["def"]))
+                .return_once(|_| {
+                    Ok(vec![ContainerSummary {
+                        id: Some("def".to_string()),
+                        ..Default::default()
+                    }])
+                });
             mock.expect_events()
-                .return_once(|_| Box::pin(futures::stream::empty()));
+                .withf(|options| {
+                    let filters = &options.as_ref().unwrap().filters;
+
+                    filters.get("label") == Some(&vec!["app=shop".to_string()])
+                        && filters.get("event") == Some(&vec!["start".to_string()])
+                        && !filters.contains_key("container")
+                })
+                .return_once(|_| {
+                    let event = EventMessage {
+                        actor: Some(EventActor {
+                            id: Some("def".to_string()),
+                            attributes: Some(HashMap::from([(
+                                "name".to_string(),
+                                "worker-2".to_string(),
+                            )])),
+                        }),
+                        time: Some(1),
+                        ..Default::default()
+                    };
+
+                    Box::pin(
+                        futures::stream::once(async { Ok(event) })
+                            .chain(futures::stream::pending()),
+                    )
+                });
+            mock.expect_logs()
+                .withf(|name, options| name == "worker-2" && options.as_ref().unwrap().since == 1)
+                .return_once(|_, _| {
+                    Box::pin(futures::stream::once(async {
+                        Ok(LogOutput::StdOut {
+                            message: "ready\n".into(),
+                        })
+                    }))
+                });
+
+            mock
+        });
+
+        let filter = ["label=app=shop".to_string(), "name=worker".to_string()];
+        let options = LogsOptions::<String> {
+            follow: true,
+            stdout: true,
+            stderr: true,
+            ..Default::default()
+        };
+
+        let events = logs(&docker, &[], &filter, options, true)
+            .await
+            .unwrap()
+            .take(2)
+            .collect::<Vec<_>>();
+
+        if cfg!(feature = "mock") {
+            let events = events
+                .await
+                .into_iter()
+                .map(Result::unwrap)
+                .collect::<Vec<_>>();
+
+            // The container started after the logs is attached
+            assert!(
+                matches!(
+                    events.as_slice(),
+                    [
+                        LogEvent::Attached(attached),
+                        LogEvent::Output {
+                            container: Some(container),
+                            output: LogOutput::StdOut { message },
+                        },
+                    ] if attached == "worker-2" && container == "worker-2" && message == "ready\n"
+                ),
+                "{:?}",
+                events
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_remove() {
+        let docker = docker_test!({
+            use mock::MockDocker;
+
+            let mut mock = MockDocker::new();
+
+            mock.expect_clone().returning(|| {
+                let mut mock = MockDocker::new();
+                mock.expect_remove_container().return_once(|_, _| Ok(()));
+
+                mock
+            });
+
+            mock
+        });
+
+        let containers = vec!["test".to_string()];
+
+        let result = rm(&docker, &containers, true, true, true, DEFAULT_PARALLEL)
+            .await
+            .summary();
+
+        assert!(result.is_ok(), "remove failed with {:?}", result);
+    }
+
+    #[tokio::test]
+    async fn test_rmi() {
+        let docker = docker_test!({
+            use mock::MockDocker;
+
+            let mut mock = MockDocker::new();
+
+            mock.expect_clone().return_once(|| {
+                let mut mock = MockDocker::new();
+
+                mock.expect_remove_image().return_once(|_, _, _| Ok(vec![]));
+
+                mock
+            });
 
             mock
         });
 
-        let result = events(&docker, &[]).await;
+        let result = rmi(&docker, &["test".to_string()], true, DEFAULT_PARALLEL)
+            .await
+            .summary();
 
-        assert!(result.is_ok(), "events failed with {:?}", result);
+        assert!(result.is_ok(), "rmi failed with {:?}", result);
     }
 }
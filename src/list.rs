@@ -1,18 +1,19 @@
-use std::{borrow::Cow, collections::HashMap, fmt::Display};
+use std::{collections::HashMap, fmt::Display};
 
-use bollard::{container::ListContainersOptions, service::ContainerSummary};
-use chrono::{NaiveDateTime, Utc};
-use color_eyre::Result;
-use prettytable::{format::FormatBuilder, Row, Table};
+use bollard::{
+    container::ListContainersOptions,
+    service::{ContainerSummary, Port},
+};
 
 #[cfg(feature = "mock")]
 use crate::mock::{DockerTrait, MockDocker as Docker};
-use crate::parse_filter;
+use crate::{error::Result, parse_filter};
 #[cfg(not(feature = "mock"))]
 use bollard::Docker;
 
+/// A size in bytes, displayed with a decimal unit like the docker CLI (e.g. `1.13kB`).
 #[derive(Debug, Default)]
-struct Size {
+pub struct Size {
     size: i64,
 }
 
@@ -45,90 +46,49 @@ impl From<i64> for Size {
     }
 }
 
-struct Stats<'a> {
-    stats: Vec<Cow<'a, str>>,
+/// A container as shown by `ps`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ContainerRow {
+    pub id: String,
+    pub image: String,
+    pub command: String,
+    /// When the container was created, in seconds since the epoch
+    pub created: i64,
+    pub status: String,
+    pub ports: Vec<Port>,
+    /// The names of the container, starting with `/`
+    pub names: Vec<String>,
+    /// The size of the writable layer, only computed with `size`
+    pub size_rw: Option<i64>,
+    /// The size of all the files of the container, only computed with `size`
+    pub size_root_fs: Option<i64>,
 }
 
-impl<'a> From<&'a ContainerSummary> for Stats<'a> {
-    fn from(value: &'a ContainerSummary) -> Self {
-        let id = value
-            .id
-            .as_ref()
-            .map(|id| id.chars().take(12).collect::<String>())
-            .unwrap_or_default();
-
-        let image = value.image.as_deref().unwrap_or_default();
-        let command = value.command.as_deref().unwrap_or_default();
-
-        let created = value
-            .created
-            .and_then(|time| NaiveDateTime::from_timestamp_opt(time, 0))
-            .and_then(|time| match time.and_local_timezone(Utc) {
-                chrono::LocalResult::Single(res) => Some(res),
-                _ => None,
-            })
-            .map(|time| {
-                let minutes = Utc::now().signed_duration_since(time).num_minutes();
-
-                format!("{} minutes ago", minutes)
-            })
-            .unwrap_or_default();
-
-        let status = value.status.as_deref().unwrap_or_default();
-
-        let ports = value
-            .ports
-            .as_ref()
-            .map(|ports| {
-                ports
-                    .iter()
-                    .map(|port| {
-                        let mut res = String::new();
-
-                        if let Some(ip) = &port.ip {
-                            res.push_str(ip);
-                            res.push(':');
-                        }
-
-                        res.push_str(&port.private_port.to_string());
-                        res.push(':');
-                        res.push_str(&port.private_port.to_string());
-
-                        res
-                    })
-                    .collect::<Vec<String>>()
-                    .join(", ")
-            })
-            .unwrap_or_default();
-
-        let names = value
-            .names
-            .as_ref()
-            .map(|names| names.join(", "))
-            .unwrap_or_default();
-
-        let size_rw = value.size_rw.map(Size::from).unwrap_or_default();
-
-        let size_root = value.size_root_fs.map(Size::from).unwrap_or_default();
-
-        let size = format!("{} (virtual {})", size_rw, size_root);
-
+impl From<ContainerSummary> for ContainerRow {
+    fn from(value: ContainerSummary) -> Self {
         Self {
-            stats: vec![
-                Cow::from(id),
-                Cow::from(image),
-                Cow::from(command),
-                Cow::from(created),
-                Cow::from(status),
-                Cow::from(ports),
-                Cow::from(names),
-                Cow::from(size),
-            ],
+            id: value.id.unwrap_or_default(),
+            image: value.image.unwrap_or_default(),
+            command: value.command.unwrap_or_default(),
+            created: value.created.unwrap_or_default(),
+            status: value.status.unwrap_or_default(),
+            ports: value.ports.unwrap_or_default(),
+            names: value.names.unwrap_or_default(),
+            size_rw: value.size_rw,
+            size_root_fs: value.size_root_fs,
         }
     }
 }
 
-pub async fn list(docker: &Docker, all: bool, size: bool, filters: &[String]) -> Result<()> {
+/// List the containers, only the running ones unless `all`.
+///
+/// The size of the containers is only computed by the daemon with `size`.
+pub async fn list(
+    docker: &Docker,
+    all: bool,
+    size: bool,
+    filters: &[String],
+) -> Result<Vec<ContainerRow>> {
     let filters: HashMap<&str, Vec<&str>> =
         filters
             .iter()
@@ -149,38 +109,7 @@ pub async fn list(docker: &Docker, all: bool, size: bool, filters: &[String]) ->
 
     let containers = docker.list_containers(Some(options)).await?;
 
-    let mut headers = vec![
-        "CONTAINER ID",
-        "IMAGE",
-        "COMMAND",
-        "CREATED",
-        "STATUS",
-        "PORTS",
-        "NAMES",
-    ];
-
-    if size {
-        headers.push("SIZE");
-    }
-
-    let format = FormatBuilder::new()
-        .column_separator(' ')
-        .padding(0, 2)
-        .build();
-
-    let mut table = Table::new();
-    table.set_format(format);
-    table.add_row(Row::from(headers));
-
-    for container in containers {
-        let stats = Stats::from(&container);
-
-        table.add_row(Row::from(stats.stats));
-    }
-
-    table.printstd();
-
-    Ok(())
+    Ok(containers.into_iter().map(ContainerRow::from).collect())
 }
 
 #[cfg(test)]
@@ -205,12 +134,31 @@ mod test {
 
             let mut mock = MockDocker::new();
 
-            mock.expect_list_containers().returning(|_| Ok(vec![]));
+            mock.expect_list_containers().returning(|_| {
+                Ok(vec![ContainerSummary {
+                    id: Some("4c01db0b339c7f8ae4ee9f9c5b9a49e2".to_string()),
+                    image: Some("nginx".to_string()),
+                    names: Some(vec!["/web".to_string()]),
+                    size_rw: Some(1125),
+                    size_root_fs: Some(1125),
+                    ..Default::default()
+                }])
+            });
 
             mock
         });
 
-        list(&docker, false, true, &[]).await?;
+        let rows = list(&docker, false, true, &[]).await?;
+
+        // The real daemon lists whatever containers are running
+        if cfg!(feature = "mock") {
+            assert_eq!(rows.len(), 1);
+            assert_eq!(rows[0].id, "4c01db0b339c7f8ae4ee9f9c5b9a49e2");
+            assert_eq!(rows[0].image, "nginx");
+            assert_eq!(rows[0].names, ["/web"]);
+            assert_eq!(rows[0].size_rw, Some(1125));
+            assert_eq!(rows[0].size_root_fs, Some(1125));
+        }
 
         Ok(())
     }
@@ -0,0 +1,469 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    pin::Pin,
+};
+
+use bollard::{
+    container::{ListContainersOptions, LogOutput, LogsOptions},
+    models::EventMessage,
+    system::EventsOptions,
+};
+use futures::{
+    stream::{self, BoxStream, SelectAll},
+    Stream, StreamExt,
+};
+use tracing::{debug, instrument};
+
+#[cfg(feature = "mock")]
+use crate::mock::{DockerTrait, MockDocker as Docker};
+use crate::{
+    error::{Error, Result},
+    parse_filter,
+};
+#[cfg(not(feature = "mock"))]
+use bollard::Docker;
+
+/// A log stream of a container, ending with `None` once the container log stream is closed.
+type ContainerStream =
+    Pin<Box<dyn Stream<Item = (String, Option<Result<LogOutput, bollard::errors::Error>>)> + Send>>;
+
+/// An item of the logs of one or more containers.
+#[derive(Debug)]
+pub enum LogEvent {
+    /// A container was attached, its lines are prefixed with its name
+    Attached(String),
+    /// A log frame, with the name of the container if the lines are prefixed
+    Output {
+        container: Option<String>,
+        output: LogOutput,
+    },
+    /// The logs of a container failed, the other containers are still followed
+    Error { container: String, error: Error },
+}
+
+/// Streams the logs of one or more containers.
+///
+/// With a single container and no filter the frames are returned as they are, otherwise the
+/// streams are merged and each container is announced with [`LogEvent::Attached`] before its
+/// frames. The error of a container is returned as a [`LogEvent::Error`] without stopping the
+/// others.
+///
+/// The frames are split on the line ends with `whole_lines`, for the lines to be filtered or
+/// formatted whole, and always when the lines are prefixed with the container names.
+///
+/// When following the logs, the containers are attached again as they restart, and the containers
+/// matching the filters are attached as they start.
+#[instrument(skip(docker))]
+pub async fn logs<'a>(
+    docker: &'a Docker,
+    containers: &[String],
+    filter: &[String],
+    options: LogsOptions<String>,
+    whole_lines: bool,
+) -> Result<BoxStream<'a, Result<LogEvent>>> {
+    let filters: HashMap<&str, Vec<&str>> =
+        filter
+            .iter()
+            .try_fold(HashMap::new(), |mut acc, filter| -> Result<_> {
+                let (filter, value) = parse_filter(filter)?;
+
+                acc.entry(filter).or_insert_with(Vec::new).push(value);
+
+                Ok(acc)
+            })?;
+
+    let mut names = containers.to_vec();
+
+    if !filters.is_empty() {
+        // The stopped containers are attached as they start when following
+        let list_options = ListContainersOptions {
+            all: !options.follow,
+            filters: filters.clone(),
+            ..Default::default()
+        };
+
+        for container in docker.list_containers(Some(list_options)).await? {
+            let name = container
+                .names
+                .and_then(|names| names.into_iter().next())
+                .map(|name| name.trim_start_matches('/').to_string());
+
+            names.extend(name);
+        }
+    }
+
+    let mut seen = HashSet::new();
+    names.retain(|name| seen.insert(name.clone()));
+
+    if let ([container], true) = (names.as_slice(), filters.is_empty()) {
+        let frames = docker.logs(container, Some(options));
+
+        // Partial lines, like prompts, are shown as they come otherwise
+        let frames = match whole_lines {
+            true => lines(frames).boxed(),
+            false => frames.boxed(),
+        };
+
+        let stream = frames.map(|output| {
+            Ok(LogEvent::Output {
+                container: None,
+                output: output?,
+            })
+        });
+
+        return Ok(stream.boxed());
+    }
+
+    // The start events of the given containers, and of the containers matching the filters, which
+    // are checked against all the filters when they start
+    let mut events = Vec::new();
+
+    if options.follow && !containers.is_empty() {
+        let filters = HashMap::from([("container", containers.to_vec())]);
+
+        events.push(
+            start_events(docker, filters)
+                .map(|event| (false, event))
+                .boxed(),
+        );
+    }
+
+    if options.follow && !filters.is_empty() {
+        let event_filters = filters
+            .iter()
+            .filter_map(|(filter, values)| {
+                // The names are matched as patterns when listing the containers, but exactly by the
+                // events, they are checked by the listing instead
+                let filter = match *filter {
+                    "label" => "label",
+                    _ => return None,
+                };
+
+                Some((
+                    filter,
+                    values.iter().map(|value| value.to_string()).collect(),
+                ))
+            })
+            .collect();
+
+        events.push(
+            start_events(docker, event_filters)
+                .map(|event| (true, event))
+                .boxed(),
+        );
+    }
+
+    let filters = filters
+        .into_iter()
+        .map(|(filter, values)| {
+            let values = values.into_iter().map(str::to_string).collect();
+
+            (filter.to_string(), values)
+        })
+        .collect();
+
+    let mut logs = Logs {
+        docker,
+        options,
+        filters,
+        streams: SelectAll::new(),
+        attached: HashSet::new(),
+        events: (!events.is_empty()).then(|| stream::select_all(events).boxed()),
+        pending: VecDeque::new(),
+    };
+
+    for name in names {
+        logs.attach(name, None);
+    }
+
+    Ok(stream::unfold(logs, Logs::next).boxed())
+}
+
+/// The start events of the containers matching the filters.
+fn start_events<'a>(
+    docker: &'a Docker,
+    filters: HashMap<&str, Vec<String>>,
+) -> BoxStream<'a, Result<EventMessage, bollard::errors::Error>> {
+    let mut filters: HashMap<_, _> = filters
+        .into_iter()
+        .map(|(filter, values)| (filter.to_string(), values))
+        .collect();
+
+    filters.insert("type".to_string(), vec!["container".to_string()]);
+    filters.insert("event".to_string(), vec!["start".to_string()]);
+
+    docker
+        .events(Some(EventsOptions {
+            filters,
+            ..Default::default()
+        }))
+        .boxed()
+}
+
+/// The merged log streams of several containers.
+struct Logs<'a> {
+    docker: &'a Docker,
+    options: LogsOptions<String>,
+    /// The filters of the containers to attach as they start.
+    filters: HashMap<String, Vec<String>>,
+    streams: SelectAll<ContainerStream>,
+    /// The containers currently attached.
+    attached: HashSet<String>,
+    /// The start events of the containers when following the logs, and whether the container
+    /// must match the filters.
+    events: Option<BoxStream<'a, (bool, Result<EventMessage, bollard::errors::Error>)>>,
+    /// The events to return before reading the streams.
+    pending: VecDeque<LogEvent>,
+}
+
+impl<'a> Logs<'a> {
+    /// Attach to the logs of a container, if it's not already attached.
+    ///
+    /// Containers attached after a start event only get the logs since the event.
+    fn attach(&mut self, name: String, since: Option<i64>) {
+        if !self.attached.insert(name.clone()) {
+            return;
+        }
+
+        debug!(name, "Attaching to container logs");
+
+        let mut options = self.options.clone();
+
+        if let Some(since) = since {
+            options.since = since;
+        }
+
+        let stream_name = name.clone();
+        let stream = lines(self.docker.logs(&name, Some(options)))
+            .map(Some)
+            .chain(stream::once(async { None }))
+            .map(move |output| (stream_name.clone(), output));
+
+        self.streams.push(Box::pin(stream));
+        self.pending.push_back(LogEvent::Attached(name));
+    }
+
+    /// Returns the next event of the merged streams, `None` once all the streams are closed or
+    /// the start events stop when following.
+    async fn next(mut self) -> Option<(Result<LogEvent>, Self)> {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Some((Ok(event), self));
+            }
+
+            let streams = &mut self.streams;
+            let events = &mut self.events;
+
+            tokio::select! {
+                Some((name, output)) = streams.next(), if !streams.is_empty() => {
+                    match output {
+                        Some(Ok(output)) => {
+                            let event = LogEvent::Output {
+                                container: Some(name),
+                                output,
+                            };
+
+                            return Some((Ok(event), self));
+                        }
+                        // The other containers are still followed
+                        Some(Err(error)) => {
+                            let event = LogEvent::Error {
+                                container: name,
+                                error: error.into(),
+                            };
+
+                            return Some((Ok(event), self));
+                        }
+                        None => {
+                            debug!(name, "Container logs closed");
+
+                            self.attached.remove(&name);
+                        }
+                    }
+                }
+                event = async { events.as_mut()?.next().await }, if events.is_some() => {
+                    let (filtered, event) = event?;
+
+                    let event = match event {
+                        Ok(event) => event,
+                        Err(error) => return Some((Err(error.into()), self)),
+                    };
+
+                    let actor = event.actor.unwrap_or_default();
+                    let name = actor
+                        .attributes
+                        .and_then(|mut attributes| attributes.remove("name"));
+
+                    let Some(name) = name else {
+                        continue;
+                    };
+
+                    if filtered {
+                        match matches(self.docker, &self.filters, actor.id).await {
+                            Ok(true) => {}
+                            Ok(false) => continue,
+                            Err(error) => {
+                                let event = LogEvent::Error {
+                                    container: name,
+                                    error,
+                                };
+
+                                return Some((Ok(event), self));
+                            }
+                        }
+                    }
+
+                    self.attach(name, event.time);
+                }
+                else => return None,
+            }
+        }
+    }
+}
+
+/// Whether a started container matches all the filters, not only the ones of the events.
+async fn matches(
+    docker: &Docker,
+    filters: &HashMap<String, Vec<String>>,
+    id: Option<String>,
+) -> Result<bool> {
+    let Some(id) = id else {
+        return Ok(false);
+    };
+
+    let mut filters: HashMap<&str, Vec<&str>> = filters
+        .iter()
+        .map(|(filter, values)| (filter.as_str(), values.iter().map(String::as_str).collect()))
+        .collect();
+
+    filters.insert("id", vec![&id]);
+
+    let options = ListContainersOptions {
+        filters,
+        ..Default::default()
+    };
+
+    Ok(!docker.list_containers(Some(options)).await?.is_empty())
+}
+
+/// Splits the frames of a log stream on the line ends, so that the lines are filtered whole.
+fn lines<S>(frames: S) -> impl Stream<Item = Result<LogOutput, bollard::errors::Error>>
+where
+    S: Stream<Item = Result<LogOutput, bollard::errors::Error>>,
+{
+    let mut lines = Lines::default();
+
+    frames
+        .map(Some)
+        .chain(stream::once(async { None }))
+        .flat_map(move |frame| {
+            let frames: Vec<_> = match frame {
+                Some(Ok(frame)) => lines.push(frame).into_iter().map(Ok).collect(),
+                Some(Err(error)) => vec![Err(error)],
+                None => lines.finish().into_iter().map(Ok).collect(),
+            };
+
+            stream::iter(frames)
+        })
+}
+
+/// The incomplete lines of each stream of a container, the daemon splits long lines across
+/// frames.
+#[derive(Debug, Default)]
+struct Lines {
+    stdout: Vec<u8>,
+    stderr: Vec<u8>,
+    console: Vec<u8>,
+}
+
+impl Lines {
+    /// Returns a frame with the complete lines, keeping the rest until the next frame of the same
+    /// stream.
+    fn push(&mut self, output: LogOutput) -> Option<LogOutput> {
+        let (buffer, message, frame): (_, _, fn(Vec<u8>) -> LogOutput) = match output {
+            LogOutput::StdOut { message } => {
+                (&mut self.stdout, message, |lines| LogOutput::StdOut {
+                    message: lines.into(),
+                })
+            }
+            LogOutput::StdErr { message } => {
+                (&mut self.stderr, message, |lines| LogOutput::StdErr {
+                    message: lines.into(),
+                })
+            }
+            LogOutput::Console { message } => {
+                (&mut self.console, message, |lines| LogOutput::Console {
+                    message: lines.into(),
+                })
+            }
+            LogOutput::StdIn { .. } => return Some(output),
+        };
+
+        buffer.extend_from_slice(&message);
+
+        let end = buffer.iter().rposition(|c| *c == b'\n')? + 1;
+        let rest = buffer.split_off(end);
+
+        Some(frame(std::mem::replace(buffer, rest)))
+    }
+
+    /// Returns the frames with the last lines without a line end, once the stream is closed.
+    fn finish(&mut self) -> Vec<LogOutput> {
+        let stdout = std::mem::take(&mut self.stdout);
+        let stderr = std::mem::take(&mut self.stderr);
+        let console = std::mem::take(&mut self.console);
+
+        [
+            (!stdout.is_empty()).then(|| LogOutput::StdOut {
+                message: stdout.into(),
+            }),
+            (!stderr.is_empty()).then(|| LogOutput::StdErr {
+                message: stderr.into(),
+            }),
+            (!console.is_empty()).then(|| LogOutput::Console {
+                message: console.into(),
+            }),
+        ]
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[tokio::test]
+    async fn test_lines() {
+        let frame = |message: &'static str| LogOutput::StdOut {
+            message: message.into(),
+        };
+
+        let frames = [
+            frame("{\"level\":"),
+            LogOutput::StdErr {
+                message: "oops\n".into(),
+            },
+            frame("\"error\"}\nnext"),
+            frame(" line"),
+        ];
+
+        let lines = lines(stream::iter(frames.map(Ok)))
+            .map(Result::unwrap)
+            .collect::<Vec<_>>()
+            .await;
+
+        assert_eq!(
+            lines,
+            [
+                LogOutput::StdErr {
+                    message: "oops\n".into()
+                },
+                frame("{\"level\":\"error\"}\n"),
+                frame("next line"),
+            ]
+        );
+    }
+}
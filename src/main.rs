@@ -1,13 +1,21 @@
-use std::env;
+use std::{env, io, process::ExitCode};
 
 use clap::Parser;
-use color_eyre::{eyre::Context, Result};
-use dockrs::cli::{Cli, Command};
+use color_eyre::{
+    eyre::{bail, Context},
+    Result,
+};
+use dockrs::{
+    cli::{Cli, Command, ContextCommand},
+    parse_duration, Error,
+};
 use tracing::metadata::LevelFilter;
 use tracing_subscriber::{prelude::*, EnvFilter};
 
+mod output;
+
 #[tokio::main]
-async fn main() -> Result<()> {
+async fn main() -> Result<ExitCode> {
     color_eyre::install()?;
 
     let mut filter = EnvFilter::try_new(env::var("RUST_LOG").as_deref().unwrap_or(""))
@@ -24,40 +32,190 @@ async fn main() -> Result<()> {
         .with(filter)
         .init();
 
-    if let Command::Completion { shell } = cli.subcommand {
-        Cli::generate_completion(shell);
+    match cli.subcommand {
+        Command::Completion { shell } => {
+            Cli::generate_completion(shell, &mut io::stdout());
+
+            return Ok(ExitCode::SUCCESS);
+        }
+        Command::Login {
+            ref server,
+            ref username,
+            ref password,
+            password_stdin,
+        } => {
+            let password = match password {
+                Some(password) => password.clone(),
+                None if password_stdin => {
+                    let mut password = String::new();
+                    io::stdin()
+                        .read_line(&mut password)
+                        .wrap_err("Failed to read the password from stdin")?;
+
+                    password.trim_end_matches(['\r', '\n']).to_string()
+                }
+                None => bail!("Must provide --password or --password-stdin"),
+            };
+
+            dockrs::login(server.as_deref(), username, &password).await?;
+
+            println!("Credentials stored, they weren't verified with the registry");
+
+            return Ok(ExitCode::SUCCESS);
+        }
+        Command::Logout { ref server } => {
+            output::logout(&dockrs::logout(server.as_deref()).await?);
+
+            return Ok(ExitCode::SUCCESS);
+        }
+        Command::Context { ref command } => {
+            match command {
+                ContextCommand::Ls => output::contexts(&dockrs::context_ls()?),
+                ContextCommand::Use { name } => {
+                    dockrs::context_use(name)?;
+
+                    println!("{}", name);
+                    eprintln!("Current context is now \"{}\"", name);
+                }
+                ContextCommand::Show => println!("{}", dockrs::context_show()?),
+                ContextCommand::Create {
+                    name,
+                    description,
+                    docker,
+                } => {
+                    dockrs::context_create(name, description.as_deref(), docker)?;
 
-        return Ok(());
+                    println!("{}", name);
+                    eprintln!("Successfully created context \"{}\"", name);
+                }
+                ContextCommand::Rm { names, force } => {
+                    dockrs::context_rm(names, *force)?;
+
+                    names.iter().for_each(|name| println!("{}", name));
+                }
+            }
+
+            return Ok(ExitCode::SUCCESS);
+        }
+        _ => {}
     }
 
-    let docker = dockrs::connect_to_docker()?;
+    let timeout = cli.timeout.as_deref().map(parse_duration).transpose()?;
+
+    let docker =
+        match dockrs::connect_to_docker(cli.host.as_deref(), cli.context.as_deref(), timeout).await
+        {
+            Ok(docker) => docker,
+            // Returned for the runtime to stop the ssh tunnel, instead of exiting
+            Err(Error::Connection(e)) => {
+                eprintln!("{}", e);
+
+                return Ok(ExitCode::FAILURE);
+            }
+            Err(e) => return Err(e.into()),
+        };
 
     match cli.subcommand {
-        Command::Run(ref run) => dockrs::run(&docker, run.into(), run.try_into()?, run.rm).await?,
-        Command::Pull { image, tag } => dockrs::pull(&docker, &image, &tag).await?,
-        Command::Stats { keep_screen } => dockrs::stats(&docker, keep_screen).await?,
+        Command::Run(run) => {
+            let args = run.as_ref();
+            let run = dockrs::Run::new(&docker, args.into(), args.try_into()?)?;
+
+            output::run(run, args.pull).await?
+        }
+        Command::Pull {
+            image,
+            tag,
+            all_tags,
+            platform,
+            quiet,
+        } => {
+            let pull = dockrs::pull(
+                &docker,
+                &image,
+                tag.as_deref(),
+                all_tags,
+                platform.as_deref(),
+            )
+            .await?;
+
+            output::pull(pull, quiet).await?
+        }
+        Command::Stats { keep_screen } => {
+            output::stats(dockrs::stats(&docker), keep_screen).await?
+        }
         Command::Start {
             containers,
             attach,
             interactive,
-        } => dockrs::start(&docker, &containers, attach, interactive).await?,
-        Command::Stop { containers } => dockrs::stop(&docker, &containers).await?,
-        Command::Ps { all, size, filter } => dockrs::list(&docker, all, size, &filter).await?,
-        Command::Logs {
-            container,
-            follow,
-            tail,
-        } => dockrs::logs(&docker, &container, follow, tail).await?,
+        } => {
+            let attached = match containers.as_slice() {
+                _ if !attach && !interactive => None,
+                [container] => Some(dockrs::attach(&docker, container, interactive).await?),
+                _ => bail!("Can only attach to one container at a time"),
+            };
+
+            output::start(
+                dockrs::start(&docker, &containers, cli.parallel).await,
+                attached,
+            )
+            .await?
+        }
+        Command::Stop {
+            time,
+            signal,
+            containers,
+        } => output::batch(
+            dockrs::stop(&docker, &containers, time, signal.as_deref(), cli.parallel).await,
+            output::target,
+        )?,
+        Command::Kill { signal, containers } => output::batch(
+            dockrs::kill(&docker, &containers, &signal, cli.parallel).await,
+            output::target,
+        )?,
+        Command::Ps { all, size, filter } => {
+            output::containers(&dockrs::list(&docker, all, size, &filter).await?, size)
+        }
+        Command::Logs(ref args) => {
+            let display: output::LogsDisplay = args.try_into()?;
+            let logs = dockrs::logs(
+                &docker,
+                &args.containers,
+                &args.filter,
+                args.try_into()?,
+                display.needs_lines(),
+            )
+            .await?;
+
+            output::logs(logs, display, args.timestamps).await?
+        }
         Command::Rm {
             containers,
             force,
             volumes,
             link,
-        } => dockrs::rm(&docker, &containers, force, volumes, link).await?,
-        Command::Rmi { images, force } => dockrs::rmi(&docker, &images, force).await?,
-        Command::Events { filter } => dockrs::events(&docker, &filter).await?,
-        Command::Completion { .. } => unreachable!(),
+        } => output::batch(
+            dockrs::rm(&docker, &containers, force, volumes, link, cli.parallel).await,
+            output::target,
+        )?,
+        Command::Rmi { images, force } => output::batch(
+            dockrs::rmi(&docker, &images, force, cli.parallel).await,
+            output::image_removed,
+        )?,
+        Command::Events(ref args) => {
+            output::events(
+                dockrs::events(&docker, args.try_into()?),
+                args.format.as_deref(),
+                args.exec.as_deref(),
+            )
+            .await?
+        }
+        Command::Completion { .. }
+        | Command::Login { .. }
+        | Command::Logout { .. }
+        | Command::Context { .. } => {
+            unreachable!()
+        }
     }
 
-    Ok(())
+    Ok(ExitCode::SUCCESS)
 }
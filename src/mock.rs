@@ -1,18 +1,20 @@
-use std::pin::Pin;
+use std::{path::Path, pin::Pin, time::Duration};
 
 use async_trait::async_trait;
 use bollard::{
     auth::DockerCredentials,
     container::{
         AttachContainerOptions, AttachContainerResults, Config, CreateContainerOptions,
-        ListContainersOptions, LogOutput, LogsOptions, RemoveContainerOptions,
-        StartContainerOptions, Stats, StatsOptions, StopContainerOptions,
+        KillContainerOptions, ListContainersOptions, LogOutput, LogsOptions,
+        RemoveContainerOptions, StartContainerOptions, Stats, StatsOptions, StopContainerOptions,
+        WaitContainerOptions,
     },
     errors::Error,
     image::{CreateImageOptions, RemoveImageOptions},
-    models::{ContainerCreateResponse, CreateImageInfo, EventMessage},
+    models::{ContainerCreateResponse, ContainerWaitResponse, CreateImageInfo, EventMessage},
     service::{ContainerSummary, ImageDeleteResponseItem},
     system::EventsOptions,
+    ClientVersion,
 };
 use futures::Stream;
 use hyper::Body;
@@ -22,7 +24,30 @@ type DockerStream<T> = Pin<Box<dyn Stream<Item = Result<T, Error>> + Send>>;
 
 #[async_trait]
 pub trait DockerTrait: Sized {
-    fn connect_with_local_defaults() -> Result<Self, Error>;
+    fn connect_with_unix(
+        path: &str,
+        timeout: u64,
+        client_version: &ClientVersion,
+    ) -> Result<Self, Error>;
+    #[cfg(windows)]
+    fn connect_with_named_pipe(
+        path: &str,
+        timeout: u64,
+        client_version: &ClientVersion,
+    ) -> Result<Self, Error>;
+    fn connect_with_http(
+        addr: &str,
+        timeout: u64,
+        client_version: &ClientVersion,
+    ) -> Result<Self, Error>;
+    fn connect_with_ssl(
+        addr: &str,
+        ssl_key: &Path,
+        ssl_cert: &Path,
+        ssl_ca: &Path,
+        timeout: u64,
+        client_version: &ClientVersion,
+    ) -> Result<Self, Error>;
     async fn remove_container(
         &self,
         container_name: &str,
@@ -34,10 +59,10 @@ pub trait DockerTrait: Sized {
         container_name: &str,
         options: Option<StartContainerOptions<&'a str>>,
     ) -> Result<(), Error>;
-    async fn create_container<'a, 'b>(
+    async fn create_container<'a>(
         &self,
         options: Option<CreateContainerOptions<&'a str>>,
-        config: Config<&'b str>,
+        config: Config<String>,
     ) -> Result<ContainerCreateResponse, Error>;
     async fn attach_container<'a>(
         &self,
@@ -60,6 +85,16 @@ pub trait DockerTrait: Sized {
         container_name: &str,
         options: Option<StopContainerOptions>,
     ) -> Result<(), Error>;
+    async fn kill_container(
+        &self,
+        container_name: &str,
+        options: Option<KillContainerOptions<String>>,
+    ) -> Result<(), Error>;
+    fn wait_container(
+        &self,
+        container_name: &str,
+        options: Option<WaitContainerOptions<String>>,
+    ) -> DockerStream<ContainerWaitResponse>;
     fn logs(
         &self,
         container_name: &str,
@@ -71,7 +106,11 @@ pub trait DockerTrait: Sized {
         options: Option<RemoveImageOptions>,
         credentials: Option<DockerCredentials>,
     ) -> Result<Vec<ImageDeleteResponseItem>, Error>;
-    fn events(&self, options: Option<EventsOptions<&str>>) -> DockerStream<EventMessage>;
+    fn events(&self, options: Option<EventsOptions<String>>) -> DockerStream<EventMessage>;
+    async fn ping(&self) -> Result<String, Error>;
+    async fn negotiate_version(self) -> Result<Self, Error>;
+    fn timeout(&self) -> Duration;
+    fn with_timeout(self, timeout: Duration) -> Self;
 }
 
 mock! {
@@ -81,8 +120,31 @@ mock! {
         fn clone(&self) -> Self;
     }
     #[async_trait]
-    impl DockerTrait  for Docker {
-        fn connect_with_local_defaults() -> Result<Self, Error>;
+    impl DockerTrait for Docker {
+        fn connect_with_unix(
+            path: &str,
+            timeout: u64,
+            client_version: &ClientVersion,
+        ) -> Result<Self, Error>;
+        #[cfg(windows)]
+        fn connect_with_named_pipe(
+            path: &str,
+            timeout: u64,
+            client_version: &ClientVersion,
+        ) -> Result<Self, Error>;
+        fn connect_with_http(
+            addr: &str,
+            timeout: u64,
+            client_version: &ClientVersion,
+        ) -> Result<Self, Error>;
+        fn connect_with_ssl(
+            addr: &str,
+            ssl_key: &Path,
+            ssl_cert: &Path,
+            ssl_ca: &Path,
+            timeout: u64,
+            client_version: &ClientVersion,
+        ) -> Result<Self, Error>;
         async fn remove_container(
             &self,
             container_name: &str,
@@ -94,10 +156,10 @@ mock! {
             container_name: &str,
             options: Option<StartContainerOptions<&'a str>>,
         ) -> Result<(), Error>;
-        async fn create_container<'a, 'b>(
+        async fn create_container<'a>(
             &self,
             options: Option<CreateContainerOptions<&'a str>>,
-            config: Config<&'b str>,
+            config: Config<String>,
         ) -> Result<ContainerCreateResponse, Error>;
         async fn attach_container<'a>(
             &self,
@@ -120,6 +182,16 @@ mock! {
             container_name: &str,
             options: Option<StopContainerOptions>,
         ) -> Result<(), Error>;
+        async fn kill_container(
+            &self,
+            container_name: &str,
+            options: Option<KillContainerOptions<String>>,
+        ) -> Result<(), Error>;
+        fn wait_container(
+            &self,
+            container_name: &str,
+            options: Option<WaitContainerOptions<String>>,
+        ) -> DockerStream<ContainerWaitResponse>;
         fn logs(
             &self,
             container_name: &str,
@@ -131,6 +203,10 @@ mock! {
             options: Option<RemoveImageOptions>,
             credentials: Option<DockerCredentials>,
         ) -> Result<Vec<ImageDeleteResponseItem>, Error>;
-        fn events<'a>(&self, options: Option<EventsOptions<&'a str>>) -> DockerStream<EventMessage>;
+        fn events(&self, options: Option<EventsOptions<String>>) -> DockerStream<EventMessage>;
+        async fn ping(&self) -> Result<String, Error>;
+        async fn negotiate_version(self) -> Result<Self, Error>;
+        fn timeout(&self) -> Duration;
+        fn with_timeout(self, timeout: Duration) -> Self;
     }
 }
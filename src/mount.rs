@@ -0,0 +1,505 @@
+use std::{
+    collections::HashMap,
+    path::{Component, Path, PathBuf},
+};
+
+use bollard::models::{
+    Mount, MountBindOptions, MountBindOptionsPropagationEnum, MountTmpfsOptions, MountTypeEnum,
+    MountVolumeOptions, MountVolumeOptionsDriverConfig,
+};
+
+use crate::error::{bail, ensure, Error, Result};
+
+const VOLUME_MODES: &[&str] = &[
+    "ro",
+    "rw",
+    "z",
+    "Z",
+    "nocopy",
+    "private",
+    "rprivate",
+    "shared",
+    "rshared",
+    "slave",
+    "rslave",
+    "consistent",
+    "cached",
+    "delegated",
+];
+
+/// Parse a `--mount` specification in the form `type=bind,source=/src,target=/dst,readonly`.
+///
+/// Relative bind sources are resolved against the directory returned by `cwd`.
+pub fn parse_mount(input: &str, cwd: impl Fn() -> Result<PathBuf>) -> Result<Mount> {
+    let mut mount = Mount {
+        typ: Some(MountTypeEnum::VOLUME),
+        ..Default::default()
+    };
+
+    let mut options = Vec::new();
+
+    for field in input.split(',') {
+        let (key, value) = match field.split_once('=') {
+            Some((key, value)) => (key, Some(value)),
+            None => (field, None),
+        };
+
+        match (key, value) {
+            ("type", Some(typ)) => {
+                mount.typ = Some(match typ {
+                    "bind" => MountTypeEnum::BIND,
+                    "volume" => MountTypeEnum::VOLUME,
+                    "tmpfs" => MountTypeEnum::TMPFS,
+                    _ => bail!(Parse, "Invalid mount type {} in {}", typ, input),
+                })
+            }
+            ("source" | "src", Some(source)) => mount.source = Some(source.to_string()),
+            ("target" | "destination" | "dst", Some(target)) => {
+                mount.target = Some(target.to_string())
+            }
+            ("readonly" | "ro", value) => {
+                mount.read_only = Some(parse_bool(value.unwrap_or("true"), input)?)
+            }
+            ("consistency", Some(consistency)) => mount.consistency = Some(consistency.to_string()),
+            (_, Some(value)) => options.push((key, value)),
+            // Bare boolean options are enabled, like `readonly`
+            ("volume-nocopy" | "bind-nonrecursive", None) => options.push((key, "true")),
+            _ => bail!(Parse, "Invalid field {} in mount {}", field, input),
+        }
+    }
+
+    let typ = mount.typ.unwrap_or(MountTypeEnum::VOLUME);
+
+    let target = mount
+        .target
+        .as_deref()
+        .ok_or_else(|| Error::Parse(format!("Missing target in mount {}", input)))?;
+
+    ensure!(
+        target.starts_with('/'),
+        Parse,
+        "Invalid mount {}: target {} must be an absolute path",
+        input,
+        target
+    );
+
+    for (key, value) in options {
+        match (typ, key) {
+            (MountTypeEnum::BIND, "bind-propagation") => {
+                bind_options(&mut mount).propagation = Some(parse_propagation(value, input)?)
+            }
+            (MountTypeEnum::BIND, "bind-nonrecursive") => {
+                bind_options(&mut mount).non_recursive = Some(parse_bool(value, input)?)
+            }
+            (MountTypeEnum::VOLUME, "volume-nocopy") => {
+                volume_options(&mut mount).no_copy = Some(parse_bool(value, input)?)
+            }
+            (MountTypeEnum::VOLUME, "volume-label") => {
+                let (label, label_value) = value.split_once('=').unwrap_or((value, ""));
+
+                volume_options(&mut mount)
+                    .labels
+                    .get_or_insert_with(HashMap::new)
+                    .insert(label.to_string(), label_value.to_string());
+            }
+            (MountTypeEnum::VOLUME, "volume-driver") => {
+                driver_config(&mut mount).name = Some(value.to_string())
+            }
+            (MountTypeEnum::VOLUME, "volume-opt") => {
+                let (opt, opt_value) = value.split_once('=').unwrap_or((value, ""));
+
+                driver_config(&mut mount)
+                    .options
+                    .get_or_insert_with(HashMap::new)
+                    .insert(opt.to_string(), opt_value.to_string());
+            }
+            (MountTypeEnum::TMPFS, "tmpfs-size") => {
+                tmpfs_options(&mut mount).size_bytes = Some(parse_size(value)?)
+            }
+            (MountTypeEnum::TMPFS, "tmpfs-mode") => {
+                let mode = i64::from_str_radix(value, 8).map_err(|_| {
+                    Error::Parse(format!("Invalid tmpfs mode {} in {}", value, input))
+                })?;
+
+                tmpfs_options(&mut mount).mode = Some(mode)
+            }
+            _ => bail!(Parse, "Invalid option {} for {} mount {}", key, typ, input),
+        }
+    }
+
+    match typ {
+        MountTypeEnum::BIND => {
+            let source = mount
+                .source
+                .as_deref()
+                .ok_or_else(|| Error::Parse(format!("Missing source in bind mount {}", input)))?;
+
+            mount.source = Some(resolve_path(source, cwd)?);
+        }
+        MountTypeEnum::TMPFS => {
+            ensure!(
+                mount.source.is_none(),
+                Parse,
+                "Invalid tmpfs mount {}: source is not supported",
+                input
+            );
+        }
+        _ => {}
+    }
+
+    Ok(mount)
+}
+
+/// Validate a `--volume` specification in the form `[source:]target[:mode]`.
+///
+/// Relative host paths (starting with `.`) are resolved against the directory returned by `cwd`,
+/// the returned string can be used as a `HostConfig` bind.
+pub fn parse_volume(input: &str, cwd: impl Fn() -> Result<PathBuf>) -> Result<String> {
+    let parts: Vec<&str> = input.split(':').collect();
+
+    let (source, target, mode) = match parts.as_slice() {
+        [target] => (None, *target, None),
+        [source, target] if target.starts_with('/') => (Some(*source), *target, None),
+        [target, mode] => (None, *target, Some(*mode)),
+        [source, target, mode] => (Some(*source), *target, Some(*mode)),
+        _ => bail!(Parse, "Invalid volume specification {}", input),
+    };
+
+    ensure!(
+        target.starts_with('/'),
+        Parse,
+        "Invalid volume specification {}: target {} must be an absolute path",
+        input,
+        target
+    );
+
+    if let Some(mode) = mode {
+        for mode in mode.split(',') {
+            ensure!(
+                VOLUME_MODES.contains(&mode),
+                Parse,
+                "Invalid mode {} in volume specification {}",
+                mode,
+                input
+            );
+        }
+    }
+
+    let source = source
+        .map(|source| -> Result<String> {
+            ensure!(
+                !source.is_empty(),
+                Parse,
+                "Invalid volume specification {}: empty source",
+                input
+            );
+
+            if source.starts_with('/') || source.starts_with('.') {
+                return resolve_path(source, cwd);
+            }
+
+            ensure!(
+                is_volume_name(source),
+                Parse,
+                "Invalid volume specification {}: {} includes invalid characters for a local volume name",
+                input,
+                source
+            );
+
+            Ok(source.to_string())
+        })
+        .transpose()?;
+
+    let bind = [source.as_deref(), Some(target), mode]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>()
+        .join(":");
+
+    Ok(bind)
+}
+
+/// Parse a `--tmpfs` specification in the form `/path[:options]`.
+pub fn parse_tmpfs(input: &str) -> Result<(String, String)> {
+    let (path, options) = input.split_once(':').unwrap_or((input, ""));
+
+    ensure!(
+        path.starts_with('/'),
+        Parse,
+        "Invalid tmpfs {}: {} must be an absolute path",
+        input,
+        path
+    );
+
+    Ok((path.to_string(), options.to_string()))
+}
+
+/// Parse a size in bytes with an optional binary unit suffix (e.g. `64m`, `1g`).
+fn parse_size(input: &str) -> Result<i64> {
+    let lower = input.to_ascii_lowercase();
+    let number = lower.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+    let unit = &lower[number.len()..];
+
+    let multiplier: i64 = match unit {
+        "" | "b" => 1,
+        "k" | "kb" => 1 << 10,
+        "m" | "mb" => 1 << 20,
+        "g" | "gb" => 1 << 30,
+        "t" | "tb" => 1 << 40,
+        _ => bail!(Parse, "Invalid size {}", input),
+    };
+
+    let number: i64 = number
+        .parse()
+        .map_err(|_| Error::Parse(format!("Invalid size {}", input)))?;
+
+    number
+        .checked_mul(multiplier)
+        .ok_or_else(|| Error::Parse(format!("Size {} is too big", input)))
+}
+
+fn parse_bool(value: &str, input: &str) -> Result<bool> {
+    match value {
+        "true" | "1" => Ok(true),
+        "false" | "0" => Ok(false),
+        _ => bail!(Parse, "Invalid boolean value {} in mount {}", value, input),
+    }
+}
+
+fn parse_propagation(value: &str, input: &str) -> Result<MountBindOptionsPropagationEnum> {
+    let propagation = match value {
+        "private" => MountBindOptionsPropagationEnum::PRIVATE,
+        "rprivate" => MountBindOptionsPropagationEnum::RPRIVATE,
+        "shared" => MountBindOptionsPropagationEnum::SHARED,
+        "rshared" => MountBindOptionsPropagationEnum::RSHARED,
+        "slave" => MountBindOptionsPropagationEnum::SLAVE,
+        "rslave" => MountBindOptionsPropagationEnum::RSLAVE,
+        _ => bail!(
+            Parse,
+            "Invalid bind propagation {} in mount {}",
+            value,
+            input
+        ),
+    };
+
+    Ok(propagation)
+}
+
+fn bind_options(mount: &mut Mount) -> &mut MountBindOptions {
+    mount.bind_options.get_or_insert_with(Default::default)
+}
+
+fn volume_options(mount: &mut Mount) -> &mut MountVolumeOptions {
+    mount.volume_options.get_or_insert_with(Default::default)
+}
+
+fn tmpfs_options(mount: &mut Mount) -> &mut MountTmpfsOptions {
+    mount.tmpfs_options.get_or_insert_with(Default::default)
+}
+
+fn driver_config(mount: &mut Mount) -> &mut MountVolumeOptionsDriverConfig {
+    volume_options(mount)
+        .driver_config
+        .get_or_insert_with(Default::default)
+}
+
+fn is_volume_name(name: &str) -> bool {
+    let mut chars = name.chars();
+
+    chars.next().is_some_and(|c| c.is_ascii_alphanumeric())
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-'))
+}
+
+/// Resolve a host path against `cwd`, removing `.` and `..` components.
+///
+/// `cwd` is only called for relative paths.
+fn resolve_path(path: &str, cwd: impl Fn() -> Result<PathBuf>) -> Result<String> {
+    let path = Path::new(path);
+    let path = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        cwd()?.join(path)
+    };
+
+    let mut resolved = PathBuf::new();
+
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                resolved.pop();
+            }
+            component => resolved.push(component),
+        }
+    }
+
+    Ok(resolved.to_string_lossy().into_owned())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    /// A working directory that is always `path`.
+    fn cwd(path: &'static str) -> impl Fn() -> Result<PathBuf> {
+        move || Ok(PathBuf::from(path))
+    }
+
+    #[test]
+    fn test_parse_mount_bind() {
+        let mount = parse_mount(
+            "type=bind,src=./data,dst=/data,readonly,bind-propagation=rshared",
+            cwd("/home/user/project"),
+        )
+        .unwrap();
+
+        let expected = Mount {
+            typ: Some(MountTypeEnum::BIND),
+            source: Some("/home/user/project/data".to_string()),
+            target: Some("/data".to_string()),
+            read_only: Some(true),
+            bind_options: Some(MountBindOptions {
+                propagation: Some(MountBindOptionsPropagationEnum::RSHARED),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        assert_eq!(mount, expected);
+    }
+
+    #[test]
+    fn test_parse_mount_volume_and_tmpfs() {
+        let mount = parse_mount(
+            "source=cache,target=/cache,volume-driver=local,volume-opt=type=nfs",
+            cwd("/"),
+        )
+        .unwrap();
+
+        let expected = Mount {
+            typ: Some(MountTypeEnum::VOLUME),
+            source: Some("cache".to_string()),
+            target: Some("/cache".to_string()),
+            volume_options: Some(MountVolumeOptions {
+                driver_config: Some(MountVolumeOptionsDriverConfig {
+                    name: Some("local".to_string()),
+                    options: Some(HashMap::from([("type".to_string(), "nfs".to_string())])),
+                }),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        assert_eq!(mount, expected);
+
+        let mount = parse_mount(
+            "type=tmpfs,target=/tmp,tmpfs-size=64m,tmpfs-mode=1770",
+            cwd("/"),
+        )
+        .unwrap();
+
+        let expected = MountTmpfsOptions {
+            size_bytes: Some(64 * 1024 * 1024),
+            mode: Some(0o1770),
+        };
+
+        assert_eq!(mount.tmpfs_options, Some(expected));
+    }
+
+    #[test]
+    fn test_parse_mount_bare_options() {
+        let mount = parse_mount("type=volume,src=v,dst=/d,volume-nocopy", cwd("/")).unwrap();
+
+        let expected = Mount {
+            typ: Some(MountTypeEnum::VOLUME),
+            source: Some("v".to_string()),
+            target: Some("/d".to_string()),
+            volume_options: Some(MountVolumeOptions {
+                no_copy: Some(true),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        assert_eq!(mount, expected);
+
+        let mount =
+            parse_mount("type=bind,src=/src,dst=/dst,ro,bind-nonrecursive", cwd("/")).unwrap();
+
+        assert_eq!(mount.read_only, Some(true));
+        assert_eq!(
+            mount.bind_options.and_then(|options| options.non_recursive),
+            Some(true)
+        );
+    }
+
+    #[test]
+    fn test_parse_mount_invalid() {
+        let cases = [
+            "type=bind,target=/data",
+            "type=tmpfs,source=foo,target=/tmp",
+            "type=foo,target=/data",
+            "source=cache,target=relative",
+            "source=cache",
+            "type=volume,target=/data,tmpfs-size=1m",
+            "type=bind,source=/src,target=/dst,readonly=maybe",
+        ];
+
+        for input in cases {
+            assert!(parse_mount(input, cwd("/")).is_err(), "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_parse_volume() {
+        let cwd = cwd("/home/user/project");
+
+        let cases = [
+            ("/data", "/data"),
+            ("cache:/cache", "cache:/cache"),
+            ("/srv:/srv:ro", "/srv:/srv:ro"),
+            (
+                "./conf:/etc/app:ro,z",
+                "/home/user/project/conf:/etc/app:ro,z",
+            ),
+            ("../shared:/shared", "/home/user/shared:/shared"),
+            ("/data:rw", "/data:rw"),
+        ];
+
+        for (input, expected) in cases {
+            assert_eq!(parse_volume(input, &cwd).unwrap(), expected, "{}", input);
+        }
+
+        let invalid = [
+            "data",
+            "cache:data",
+            "/src:/dst:foo",
+            "my volume:/data",
+            "a:b:c:d",
+            ":/data",
+        ];
+
+        for input in invalid {
+            assert!(parse_volume(input, &cwd).is_err(), "{}", input);
+        }
+
+        let deleted = || -> Result<PathBuf> { bail!(Parse, "No working directory") };
+
+        assert_eq!(parse_volume("/srv:/srv", deleted).unwrap(), "/srv:/srv");
+        assert!(parse_volume("./srv:/srv", deleted).is_err());
+    }
+
+    #[test]
+    fn test_parse_tmpfs() {
+        assert_eq!(
+            parse_tmpfs("/run:rw,size=64m").unwrap(),
+            ("/run".to_string(), "rw,size=64m".to_string())
+        );
+        assert_eq!(
+            parse_tmpfs("/tmp").unwrap(),
+            ("/tmp".to_string(), String::new())
+        );
+        assert!(parse_tmpfs("tmp").is_err());
+    }
+}
@@ -0,0 +1,1213 @@
+//! Printing of the results of the library operations.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    io::{self, IsTerminal, Write},
+    time::Duration,
+};
+
+use bollard::{
+    container::{LogOutput, Stats},
+    models::{CreateImageInfo, EventMessage, ProgressDetail},
+    service::ImageDeleteResponseItem,
+};
+use chrono::{Local, SecondsFormat, TimeZone, Utc};
+use color_eyre::{
+    eyre::{bail, eyre},
+    Result,
+};
+use crossterm::{
+    cursor::{MoveDown, MoveToRow, MoveUp},
+    execute, queue,
+    style::{Color, Print, Stylize},
+    terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use dockrs::{
+    cli::LogsArgs, Attached, Batch, ContainerRow, ContextRow, Error, LogEvent, Logout, Outcome,
+    Pull, PullPolicy, Run, Size, StatsEvent,
+};
+use futures::{Stream, StreamExt};
+use prettytable::{format::FormatBuilder, Row, Table};
+use regex::Regex;
+use serde_json::{Map, Value};
+
+/// Colors used for the container name prefixes of the logs, in the order the containers are
+/// attached.
+const PREFIX_COLORS: [Color; 6] = [
+    Color::Cyan,
+    Color::Yellow,
+    Color::Green,
+    Color::Magenta,
+    Color::Blue,
+    Color::DarkCyan,
+];
+
+/// A table with the columns separated by spaces, like the docker CLI.
+fn table(headers: &[&str]) -> Table {
+    let format = FormatBuilder::new()
+        .column_separator(' ')
+        .padding(0, 2)
+        .build();
+
+    let mut table = Table::new();
+    table.set_format(format);
+    table.add_row(Row::from(headers));
+
+    table
+}
+
+/// Prints the containers, with their size if requested.
+pub(crate) fn containers(containers: &[ContainerRow], size: bool) {
+    let mut headers = vec![
+        "CONTAINER ID",
+        "IMAGE",
+        "COMMAND",
+        "CREATED",
+        "STATUS",
+        "PORTS",
+        "NAMES",
+    ];
+
+    if size {
+        headers.push("SIZE");
+    }
+
+    let mut table = table(&headers);
+
+    for container in containers {
+        let id = container.id.chars().take(12).collect::<String>();
+
+        let created = Utc
+            .timestamp_opt(container.created, 0)
+            .single()
+            .map(|time| {
+                let minutes = Utc::now().signed_duration_since(time).num_minutes();
+
+                format!("{} minutes ago", minutes)
+            })
+            .unwrap_or_default();
+
+        let ports = container
+            .ports
+            .iter()
+            .map(|port| {
+                let mut res = String::new();
+
+                if let Some(ip) = &port.ip {
+                    res.push_str(ip);
+                    res.push(':');
+                }
+
+                res.push_str(&port.private_port.to_string());
+                res.push(':');
+                res.push_str(&port.private_port.to_string());
+
+                res
+            })
+            .collect::<Vec<String>>()
+            .join(", ");
+
+        let names = container.names.join(", ");
+
+        let mut row = vec![
+            id,
+            container.image.clone(),
+            container.command.clone(),
+            created,
+            container.status.clone(),
+            ports,
+            names,
+        ];
+
+        if size {
+            let size_rw = container.size_rw.map(Size::from).unwrap_or_default();
+            let size_root = container.size_root_fs.map(Size::from).unwrap_or_default();
+
+            row.push(format!("{} (virtual {})", size_rw, size_root));
+        }
+
+        table.add_row(Row::from(row));
+    }
+
+    table.printstd();
+}
+
+/// Prints the contexts, marking the current one with `*`.
+pub(crate) fn contexts(contexts: &[ContextRow]) {
+    let mut table = table(&["NAME", "DESCRIPTION", "DOCKER ENDPOINT"]);
+
+    for context in contexts {
+        let name = if context.current {
+            format!("{} *", context.name)
+        } else {
+            context.name.clone()
+        };
+
+        table.add_row(Row::from([&name, &context.description, &context.endpoint]));
+    }
+
+    table.printstd();
+}
+
+/// Prints the progress of a pull, or only the pulled image if `quiet`.
+pub(crate) async fn pull(pull: Pull<'_>, quiet: bool) -> Result<()> {
+    let Pull {
+        image,
+        mut progress,
+    } = pull;
+
+    let mut display = (!quiet).then(|| PullProgress::new(io::stdout(), io::stdout().is_terminal()));
+
+    while let Some(info) = progress.next().await {
+        let info = info?;
+
+        match display.as_mut() {
+            Some(display) => display.update(&info)?,
+            None => {
+                if let Some(error) = info.error {
+                    bail!("{}", error);
+                }
+            }
+        }
+    }
+
+    if quiet {
+        println!("{}", image);
+    }
+
+    Ok(())
+}
+
+const BAR_WIDTH: i64 = 40;
+
+/// Displays the progress of an image pull, with a line for each layer.
+///
+/// On a terminal the layer lines are updated in place, otherwise a line is printed only when the
+/// status of a layer changes.
+pub(crate) struct PullProgress<W> {
+    out: W,
+    tty: bool,
+    /// The height of the terminal, lines that scrolled out of it can't be updated.
+    height: Option<usize>,
+    /// The layers in the order they were received.
+    layers: Vec<Layer>,
+    /// The number of lines printed so far.
+    lines: usize,
+}
+
+struct Layer {
+    id: String,
+    /// The last status received for the layer
+    status: String,
+    /// The index of the line showing the layer
+    line: usize,
+}
+
+impl<W: Write> PullProgress<W> {
+    pub(crate) fn new(out: W, tty: bool) -> Self {
+        let height = tty
+            .then(terminal::size)
+            .and_then(|size| size.ok())
+            .map(|(_, height)| usize::from(height));
+
+        Self {
+            out,
+            tty,
+            height,
+            layers: Vec::new(),
+            lines: 0,
+        }
+    }
+
+    fn print_line(&mut self, line: &str) -> Result<()> {
+        writeln!(self.out, "{}", line)?;
+
+        self.lines += 1;
+
+        Ok(())
+    }
+
+    pub(crate) fn update(&mut self, info: &CreateImageInfo) -> Result<()> {
+        if let Some(error) = &info.error {
+            bail!("{}", error);
+        }
+
+        let status = info.status.as_deref().unwrap_or_default();
+
+        let id = match &info.id {
+            Some(id) => id,
+            None => {
+                self.print_line(status)?;
+
+                return Ok(self.out.flush()?);
+            }
+        };
+
+        let position = self.layers.iter().position(|layer| &layer.id == id);
+
+        match position {
+            Some(position) if self.tty => {
+                let line = layer_line(id, status, info.progress_detail.as_ref());
+                let up = self.lines - self.layers[position].line;
+
+                // Lines that scrolled out of the terminal can't be reached anymore
+                match u16::try_from(up) {
+                    Ok(up) if self.height.is_none_or(|height| usize::from(up) < height) => {
+                        queue!(
+                            self.out,
+                            MoveUp(up),
+                            Clear(ClearType::CurrentLine),
+                            Print(line),
+                            Print('\r'),
+                            MoveDown(up)
+                        )?;
+                    }
+                    _ => {
+                        self.layers[position].line = self.lines;
+
+                        self.print_line(&line)?;
+                    }
+                }
+            }
+            Some(position) => {
+                if self.layers[position].status != status {
+                    self.layers[position].status = status.to_string();
+
+                    self.print_line(&format!("{}: {}", id, status))?;
+                }
+            }
+            None => {
+                let line = if self.tty {
+                    layer_line(id, status, info.progress_detail.as_ref())
+                } else {
+                    format!("{}: {}", id, status)
+                };
+
+                self.layers.push(Layer {
+                    id: id.clone(),
+                    status: status.to_string(),
+                    line: self.lines,
+                });
+
+                self.print_line(&line)?;
+            }
+        }
+
+        Ok(self.out.flush()?)
+    }
+}
+
+/// Formats a layer line with a progress bar, if the progress details are available.
+fn layer_line(id: &str, status: &str, detail: Option<&ProgressDetail>) -> String {
+    match detail {
+        Some(ProgressDetail {
+            current: Some(current),
+            total: Some(total),
+        }) if *total > 0 => {
+            let filled = (current.min(total) * BAR_WIDTH / total) as usize;
+            let width = BAR_WIDTH as usize;
+
+            let bar = if filled < width {
+                format!("{}>{}", "=".repeat(filled), " ".repeat(width - filled - 1))
+            } else {
+                "=".repeat(width)
+            };
+
+            format!(
+                "{}: {} [{}] {}/{}",
+                id,
+                status,
+                bar,
+                Size::from(*current),
+                Size::from(*total)
+            )
+        }
+        _ => format!("{}: {}", id, status),
+    }
+}
+
+/// Runs a container, printing the progress of the pulls of its image.
+pub(crate) async fn run(run: Run<'_>, policy: PullPolicy) -> Result<()> {
+    if policy == PullPolicy::Always {
+        pull(run.pull().await, false).await?;
+    }
+
+    let id = match run.create().await? {
+        Some(id) => id,
+        None if policy == PullPolicy::Missing => {
+            eprintln!("Unable to find image '{}' locally", run.image());
+
+            pull(run.pull().await, false).await?;
+
+            match run.create().await? {
+                Some(id) => id,
+                None => bail!("No such image: {}", run.image()),
+            }
+        }
+        None => bail!("No such image: {}", run.image()),
+    };
+
+    attached(run.start(&id).await?).await
+}
+
+/// Prints the started containers, once the output of the attached container ends, forwarding the
+/// process stdin to it when attached interactively.
+pub(crate) async fn start(batch: Batch<()>, attached: Option<Attached>) -> Result<()> {
+    // The container isn't running if it failed to start
+    if let (Some(Attached { output, input }), None) = (attached, batch.failed().next()) {
+        let forward = input.map(|mut input| {
+            tokio::spawn(async move { tokio::io::copy(&mut tokio::io::stdin(), &mut input).await })
+        });
+
+        let result = self::attached(output).await;
+
+        if let Some(forward) = forward {
+            forward.abort();
+        }
+
+        result?;
+    }
+
+    self::batch(batch, target)
+}
+
+/// Writes the output of a container to the process stdout and stderr.
+async fn attached(output: impl Stream<Item = dockrs::Result<LogOutput>>) -> Result<()> {
+    futures::pin_mut!(output);
+
+    while let Some(output) = output.next().await {
+        match output? {
+            LogOutput::StdErr { message } => {
+                let mut stderr = io::stderr();
+
+                stderr.write_all(&message)?;
+                stderr.flush()?;
+            }
+            LogOutput::StdOut { message } | LogOutput::Console { message } => {
+                let mut stdout = io::stdout();
+
+                stdout.write_all(&message)?;
+                stdout.flush()?;
+            }
+            LogOutput::StdIn { .. } => unreachable!("We didn't ask for stdin"),
+        }
+    }
+
+    Ok(())
+}
+
+/// How the log lines are filtered and displayed.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct LogsDisplay {
+    /// Highlight the stderr lines, the timestamps and the prefixes.
+    color: bool,
+    /// Render the JSON lines as aligned timestamp, level and message columns.
+    pretty: bool,
+    /// Only show the lines matching this regex.
+    grep: Option<Regex>,
+    /// Only show the JSON lines with these field values, fields can be nested (e.g. `http.status`).
+    json_fields: Vec<(String, String)>,
+}
+
+impl LogsDisplay {
+    /// Whether the lines are filtered or formatted, which needs the frames to hold whole lines.
+    pub(crate) fn needs_lines(&self) -> bool {
+        self.color || self.pretty || self.grep.is_some() || !self.json_fields.is_empty()
+    }
+
+    /// Filters and formats each line of a log frame.
+    fn format(&self, message: &[u8], is_stderr: bool, timestamps: bool) -> Vec<u8> {
+        if !self.needs_lines() {
+            return message.to_vec();
+        }
+
+        let message = String::from_utf8_lossy(message);
+        let mut out = String::with_capacity(message.len());
+
+        for line in message.split_inclusive('\n') {
+            let (line, newline) = match line.strip_suffix('\n') {
+                Some(line) => (line, "\n"),
+                None => (line, ""),
+            };
+
+            let (timestamp, content) = match line.split_once(' ') {
+                Some((timestamp, content)) if timestamps => (Some(timestamp), content),
+                _ => (None, line),
+            };
+
+            if self
+                .grep
+                .as_ref()
+                .is_some_and(|grep| !grep.is_match(content))
+            {
+                continue;
+            }
+
+            let json = if self.pretty || !self.json_fields.is_empty() {
+                serde_json::from_str::<Value>(content.trim()).ok()
+            } else {
+                None
+            };
+
+            if !self.json_fields.is_empty() && !json.as_ref().is_some_and(|json| self.matches(json))
+            {
+                continue;
+            }
+
+            match json {
+                Some(Value::Object(fields)) if self.pretty => {
+                    out.push_str(&self.pretty_line(timestamp, fields, is_stderr));
+                }
+                _ => {
+                    if let Some(timestamp) = timestamp {
+                        out.push_str(&self.timestamp(timestamp));
+                        out.push(' ');
+                    }
+
+                    if is_stderr && self.color {
+                        out.push_str(&content.red().to_string());
+                    } else {
+                        out.push_str(content);
+                    }
+                }
+            }
+
+            out.push_str(newline);
+        }
+
+        out.into_bytes()
+    }
+
+    /// Checks if a JSON line has all the field values.
+    fn matches(&self, json: &Value) -> bool {
+        self.json_fields.iter().all(|(field, expected)| {
+            let pointer = format!("/{}", field.replace('.', "/"));
+
+            match json.pointer(&pointer) {
+                Some(Value::String(value)) => value == expected,
+                Some(value) => expected
+                    .parse::<Value>()
+                    .is_ok_and(|expected| *value == expected),
+                None => false,
+            }
+        })
+    }
+
+    /// Renders a JSON line as `timestamp level message key=value...`.
+    ///
+    /// The timestamp of the daemon is used if present, otherwise the one of the line.
+    fn pretty_line(
+        &self,
+        timestamp: Option<&str>,
+        mut fields: Map<String, Value>,
+        is_stderr: bool,
+    ) -> String {
+        let mut take = |keys: &[&str]| {
+            keys.iter()
+                .find_map(|key| fields.remove(*key))
+                .map(|value| match value {
+                    Value::String(value) => value,
+                    value => value.to_string(),
+                })
+                .unwrap_or_default()
+        };
+
+        let time = take(&["time", "timestamp", "ts", "@timestamp"]);
+        let level = take(&["level", "lvl", "severity"]).to_uppercase();
+        let message = take(&["msg", "message"]);
+
+        let timestamp = format!("{:30}", timestamp.unwrap_or(&time));
+        let level_column = format!("{:5}", level);
+
+        let level_column = match level.as_str() {
+            _ if !self.color => level_column,
+            "ERROR" | "FATAL" | "PANIC" | "CRITICAL" => level_column.red().to_string(),
+            "WARN" | "WARNING" => level_column.yellow().to_string(),
+            "INFO" => level_column.green().to_string(),
+            "DEBUG" | "TRACE" => level_column.blue().to_string(),
+            _ => level_column,
+        };
+
+        let mut line = format!("{} {} ", self.timestamp(&timestamp), level_column);
+
+        if is_stderr && self.color {
+            line.push_str(&message.red().to_string());
+        } else {
+            line.push_str(&message);
+        }
+
+        for (key, value) in fields {
+            let value = match value {
+                Value::String(value) => value,
+                value => value.to_string(),
+            };
+
+            line.push_str(&format!(" {}={}", key, value));
+        }
+
+        line
+    }
+
+    fn timestamp(&self, timestamp: &str) -> String {
+        if self.color {
+            timestamp.dark_grey().to_string()
+        } else {
+            timestamp.to_string()
+        }
+    }
+}
+
+impl TryFrom<&LogsArgs> for LogsDisplay {
+    type Error = color_eyre::Report;
+
+    fn try_from(args: &LogsArgs) -> Result<Self, Self::Error> {
+        let grep = args
+            .grep
+            .as_deref()
+            .map(|grep| Regex::new(grep).map_err(|e| eyre!("Invalid regex {}: {}", grep, e)))
+            .transpose()?;
+
+        let json_fields = args
+            .json_field
+            .iter()
+            .map(|field| {
+                field
+                    .split_once('=')
+                    .map(|(field, value)| (field.to_string(), value.to_string()))
+                    .ok_or_else(|| eyre!("Invalid JSON field filter {}", field))
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Self {
+            color: args.color,
+            pretty: args.pretty,
+            grep,
+            json_fields,
+        })
+    }
+}
+
+/// Prints the logs, writing the stderr frames to the process stderr.
+///
+/// The lines of the attached containers are prefixed with their name, and are filtered and
+/// formatted according to the display options. The errors of the containers are printed as they
+/// come, failing once the logs end if any of them failed.
+pub(crate) async fn logs(
+    logs: impl Stream<Item = dockrs::Result<LogEvent>>,
+    display: LogsDisplay,
+    timestamps: bool,
+) -> Result<()> {
+    futures::pin_mut!(logs);
+
+    let mut colors = HashMap::new();
+    let mut width = 0;
+    let mut failed = Vec::new();
+
+    while let Some(event) = logs.next().await {
+        let (name, output) = match event? {
+            LogEvent::Attached(name) => {
+                width = width.max(name.len());
+
+                let color = PREFIX_COLORS[colors.len() % PREFIX_COLORS.len()];
+                colors.entry(name).or_insert(color);
+
+                continue;
+            }
+            LogEvent::Output { container, output } => (container, output),
+            LogEvent::Error { container, error } => {
+                print_error(&error);
+
+                if !failed.contains(&container) {
+                    failed.push(container);
+                }
+
+                continue;
+            }
+        };
+
+        let (message, is_stderr) = match output {
+            LogOutput::StdErr { message } => (message, true),
+            LogOutput::StdOut { message } | LogOutput::Console { message } => (message, false),
+            LogOutput::StdIn { .. } => unreachable!("We didn't ask for stdin"),
+        };
+
+        let mut message = display.format(&message, is_stderr, timestamps);
+
+        if let Some(name) = name {
+            let prefix = format!("{:width$} | ", name, width = width);
+
+            let prefix = match colors.get(&name) {
+                Some(color) if display.color => prefix.with(*color).to_string(),
+                _ => prefix,
+            };
+
+            message = prefix_lines(&message, &prefix);
+        }
+
+        if is_stderr {
+            let mut stderr = io::stderr();
+
+            stderr.write_all(&message)?;
+            stderr.flush()?;
+        } else {
+            let mut stdout = io::stdout();
+
+            stdout.write_all(&message)?;
+            stdout.flush()?;
+        }
+    }
+
+    if !failed.is_empty() {
+        bail!("Failed to get the logs of {}", failed.join(", "));
+    }
+
+    Ok(())
+}
+
+/// Adds a prefix to each line of a log frame.
+fn prefix_lines(message: &[u8], prefix: &str) -> Vec<u8> {
+    let mut out = Vec::with_capacity(message.len() + prefix.len());
+
+    for line in message.split_inclusive(|c| *c == b'\n') {
+        out.extend_from_slice(prefix.as_bytes());
+        out.extend_from_slice(line);
+    }
+
+    out
+}
+
+/// Prints a table with the last stats of each container every 100ms, on the alternate screen
+/// unless `keep_screen`.
+pub(crate) async fn stats(
+    stats: impl Stream<Item = dockrs::Result<StatsEvent>>,
+    keep_screen: bool,
+) -> Result<()> {
+    futures::pin_mut!(stats);
+
+    let mut containers = BTreeMap::new();
+    let mut interval = tokio::time::interval(Duration::from_millis(100));
+
+    if !keep_screen {
+        execute!(io::stdout(), EnterAlternateScreen)?;
+
+        tokio::spawn(async move {
+            tokio::signal::ctrl_c().await.unwrap();
+            execute!(io::stdout(), LeaveAlternateScreen).unwrap();
+            std::process::exit(0);
+        });
+    }
+
+    loop {
+        tokio::select! {
+            stat = stats.next() => {
+                let Some(stat) = stat else {
+                    break;
+                };
+
+                match stat? {
+                    StatsEvent::Update(stat) => {
+                        containers.insert(stat.id.clone(), *stat);
+                    }
+                    // The container stopped or was removed
+                    StatsEvent::Ended(id) => {
+                        containers.remove(&id);
+                    }
+                }
+            }
+            _ = interval.tick() => {
+                if !keep_screen {
+                    execute!(io::stdout(), Clear(ClearType::All), MoveToRow(0))?;
+                }
+
+                println!("Container ID\tName\tCPU\tMemory\tNetwork\tBlock I/O");
+
+                containers.values().for_each(stats_line);
+            }
+        }
+    }
+
+    if !keep_screen {
+        execute!(io::stdout(), LeaveAlternateScreen)?;
+    }
+
+    Ok(())
+}
+
+/// Prints the stats of a container.
+fn stats_line(stat: &Stats) {
+    let cpu = stat.cpu_stats.cpu_usage.total_usage;
+
+    let memory = stat
+        .memory_stats
+        .usage
+        .map(|s| s.to_string())
+        .unwrap_or_else(|| "-".to_string());
+
+    let net = stat
+        .network
+        .map(|s| s.rx_bytes.to_string())
+        .unwrap_or_else(|| "-".to_string());
+
+    let read = stat
+        .storage_stats
+        .read_size_bytes
+        .map_or_else(|| "-".to_string(), |s| s.to_string());
+    let write = stat
+        .storage_stats
+        .write_size_bytes
+        .map_or_else(|| "-".to_string(), |s| s.to_string());
+
+    println!(
+        "{}\t{}\t{}\t{}\t{}\t{}/{}",
+        stat.id, stat.name, cpu, memory, net, read, write
+    );
+}
+
+/// Prints the outcome of a logout.
+pub(crate) fn logout(logout: &Logout) {
+    match logout {
+        Logout::Removed(registry) => println!("Removing login credentials for {}", registry),
+        Logout::NotLoggedIn(registry) => println!("Not logged in to {}", registry),
+    }
+}
+
+/// Prints the outcomes of a batch command in the order of the targets, the successful ones with
+/// `print` and the errors on stderr, failing with a summary if any of the targets failed.
+pub(crate) fn batch<T>(batch: Batch<T>, print: impl Fn(&str, T)) -> Result<()> {
+    let summary = batch.summary();
+
+    for Outcome { target, result } in batch.outcomes {
+        match result {
+            Ok(value) => print(&target, value),
+            Err(err) => print_error(&err),
+        }
+    }
+
+    Ok(summary?)
+}
+
+/// Prints the error of a target, like the docker CLI for the errors of the daemon.
+fn print_error(error: &Error) {
+    match error {
+        Error::NotFound { message, .. }
+        | Error::Conflict { message, .. }
+        | Error::Docker(bollard::errors::Error::DockerResponseServerError { message, .. }) => {
+            eprintln!("Error response from daemon: {}", message)
+        }
+        error => eprintln!("{}", error),
+    }
+}
+
+/// Prints a target that succeeded, e.g. a stopped container.
+pub(crate) fn target(target: &str, _: ()) {
+    println!("{}", target);
+}
+
+/// Prints the untagged and deleted references of an image.
+pub(crate) fn image_removed(_image: &str, responses: Vec<ImageDeleteResponseItem>) {
+    for response in responses {
+        if let Some(untagged) = response.untagged {
+            println!("Untagged: {}", untagged);
+        }
+
+        if let Some(deleted) = response.deleted {
+            println!("Deleted: {}", deleted);
+        }
+    }
+}
+
+/// Prints the events in the `format` as they are received, running the `exec` command for each
+/// of them.
+pub(crate) async fn events(
+    events: impl Stream<Item = dockrs::Result<EventMessage>>,
+    format: Option<&str>,
+    exec: Option<&str>,
+) -> Result<()> {
+    futures::pin_mut!(events);
+
+    while let Some(event) = events.next().await {
+        let event = event?;
+
+        println!("{}", format_event(&event, format)?);
+
+        if let Some(exec) = exec {
+            dockrs::spawn_event_command(exec, &event)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Formats an event for display.
+///
+/// The `format` is either `json`, to format the event as a JSON line, or a template (e.g.
+/// `{{.Type}} {{.Actor.Attributes.name}}`). By default the event is formatted with its
+/// timestamp in the local timezone.
+fn format_event(event: &EventMessage, format: Option<&str>) -> Result<String> {
+    let line = match format {
+        None => event_line(event, &Local),
+        Some("json") => serde_json::to_string(event)?,
+        Some(format) => dockrs::render_template(format, &serde_json::to_value(event)?)?,
+    };
+
+    Ok(line)
+}
+
+/// Formats an event as `time type action id (attributes)`, using the time of the daemon.
+fn event_line<Tz: TimeZone>(event: &EventMessage, timezone: &Tz) -> String
+where
+    Tz::Offset: std::fmt::Display,
+{
+    let time = match (event.time_nano, event.time) {
+        (Some(nanos), _) => Utc.timestamp_nanos(nanos),
+        (None, Some(secs)) => Utc.timestamp_opt(secs, 0).single().unwrap_or_default(),
+        (None, None) => Utc::now(),
+    };
+
+    let typ = event.typ.map(|t| t.to_string()).unwrap_or_default();
+
+    let action = event.action.as_deref().unwrap_or_default();
+
+    let actor_id = event
+        .actor
+        .as_ref()
+        .and_then(|actor| actor.id.as_deref())
+        .unwrap_or("");
+
+    let actor_attributes = event
+        .actor
+        .as_ref()
+        .and_then(|actor| actor.attributes.as_ref())
+        .map(|attr| {
+            let mut attr = attr
+                .iter()
+                .map(|(k, v)| format!("{}={}", k, v))
+                .collect::<Vec<_>>();
+
+            attr.sort();
+
+            attr.join(", ")
+        })
+        .unwrap_or_default();
+
+    format!(
+        "{} {} {} {} ({})",
+        time.with_timezone(timezone)
+            .to_rfc3339_opts(SecondsFormat::Nanos, true),
+        typ,
+        action,
+        actor_id,
+        actor_attributes
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use bollard::models::{EventActor, EventMessageTypeEnum};
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_prefix_lines() {
+        assert_eq!(
+            prefix_lines(b"one\ntwo\n", "web | "),
+            b"web | one\nweb | two\n".to_vec()
+        );
+        assert_eq!(prefix_lines(b"", "web | "), Vec::<u8>::new());
+    }
+
+    fn info(id: Option<&str>, status: &str, current: Option<i64>) -> CreateImageInfo {
+        CreateImageInfo {
+            id: id.map(str::to_string),
+            status: Some(status.to_string()),
+            progress_detail: current.map(|current| ProgressDetail {
+                current: Some(current),
+                total: Some(2000),
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_layer_line() {
+        let detail = ProgressDetail {
+            current: Some(1000),
+            total: Some(2000),
+        };
+
+        let line = layer_line("abc", "Downloading", Some(&detail));
+
+        let expected = format!(
+            "abc: Downloading [{}>{}] 1000B/2.00kB",
+            "=".repeat(20),
+            " ".repeat(19)
+        );
+
+        assert_eq!(line, expected);
+        assert_eq!(
+            layer_line("abc", "Pull complete", None),
+            "abc: Pull complete"
+        );
+    }
+
+    #[test]
+    fn test_pull_progress_plain() {
+        let mut progress = PullProgress::new(Vec::new(), false);
+
+        let infos = [
+            info(Some("latest"), "Pulling from library/alpine", None),
+            info(Some("abc"), "Downloading", Some(10)),
+            info(Some("abc"), "Downloading", Some(20)),
+            info(Some("abc"), "Pull complete", None),
+            info(
+                None,
+                "Status: Downloaded newer image for alpine:latest",
+                None,
+            ),
+        ];
+
+        for info in &infos {
+            progress.update(info).unwrap();
+        }
+
+        let expected = "latest: Pulling from library/alpine\n\
+                        abc: Downloading\n\
+                        abc: Pull complete\n\
+                        Status: Downloaded newer image for alpine:latest\n";
+
+        assert_eq!(String::from_utf8(progress.out).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_pull_progress_tty() {
+        let mut progress = PullProgress::new(Vec::new(), true);
+
+        progress
+            .update(&info(Some("abc"), "Downloading", Some(10)))
+            .unwrap();
+        progress
+            .update(&info(Some("def"), "Waiting", None))
+            .unwrap();
+        progress
+            .update(&info(Some("abc"), "Pull complete", None))
+            .unwrap();
+
+        let out = String::from_utf8(progress.out).unwrap();
+
+        // The first layer is updated in place, two lines above the cursor
+        assert!(
+            out.ends_with("\x1b[2A\x1b[2Kabc: Pull complete\r\x1b[2B"),
+            "{:?}",
+            out
+        );
+        assert_eq!(out.matches('\n').count(), 2);
+    }
+
+    #[test]
+    fn test_pull_progress_tty_status() {
+        let mut progress = PullProgress::new(Vec::new(), true);
+
+        progress
+            .update(&info(Some("abc"), "Downloading", Some(10)))
+            .unwrap();
+        progress
+            .update(&info(None, "Digest: sha256:123", None))
+            .unwrap();
+        progress
+            .update(&info(Some("abc"), "Pull complete", None))
+            .unwrap();
+
+        let out = String::from_utf8(progress.out).unwrap();
+
+        // The status line is skipped when going up to the layer
+        assert!(
+            out.ends_with("\x1b[2A\x1b[2Kabc: Pull complete\r\x1b[2B"),
+            "{:?}",
+            out
+        );
+    }
+
+    #[test]
+    fn test_pull_progress_error() {
+        let mut progress = PullProgress::new(Vec::new(), false);
+
+        let info = CreateImageInfo {
+            error: Some("manifest unknown".to_string()),
+            ..Default::default()
+        };
+
+        assert!(progress.update(&info).is_err());
+    }
+
+    #[test]
+    fn test_format_color() {
+        let message = b"2023-05-01T10:00:00.000000000Z hello\n";
+
+        let display = LogsDisplay::default();
+
+        assert_eq!(display.format(message, true, true), message.to_vec());
+
+        let display = LogsDisplay {
+            color: true,
+            ..Default::default()
+        };
+
+        let out = String::from_utf8(display.format(message, false, true)).unwrap();
+
+        assert_eq!(
+            out,
+            "\x1b[38;5;8m2023-05-01T10:00:00.000000000Z\x1b[39m hello\n"
+        );
+
+        let out = String::from_utf8(display.format(b"oops\nagain", true, false)).unwrap();
+
+        assert_eq!(out, "\x1b[38;5;9moops\x1b[39m\n\x1b[38;5;9magain\x1b[39m");
+    }
+
+    #[test]
+    fn test_format_filter() {
+        let message = br#"{"level":"error","msg":"failed","http":{"status":500}}
+{"level":"info","msg":"started"}
+plain error line
+"#;
+
+        let display = LogsDisplay {
+            grep: Some(Regex::new("error").unwrap()),
+            ..Default::default()
+        };
+
+        let out = String::from_utf8(display.format(message, false, false)).unwrap();
+
+        assert_eq!(
+            out,
+            "{\"level\":\"error\",\"msg\":\"failed\",\"http\":{\"status\":500}}\nplain error line\n"
+        );
+
+        let display = LogsDisplay {
+            json_fields: vec![
+                ("level".to_string(), "error".to_string()),
+                ("http.status".to_string(), "500".to_string()),
+            ],
+            ..Default::default()
+        };
+
+        let out = String::from_utf8(display.format(message, false, false)).unwrap();
+
+        assert_eq!(
+            out,
+            "{\"level\":\"error\",\"msg\":\"failed\",\"http\":{\"status\":500}}\n"
+        );
+    }
+
+    #[test]
+    fn test_format_pretty() {
+        let message = br#"2023-05-01T10:00:00Z {"level":"info","msg":"started","port":8080}
+2023-05-01T10:00:01Z not json
+"#;
+
+        let display = LogsDisplay {
+            pretty: true,
+            ..Default::default()
+        };
+
+        let out = String::from_utf8(display.format(message, false, true)).unwrap();
+
+        assert_eq!(
+            out,
+            "2023-05-01T10:00:00Z           INFO  started port=8080\n\
+             2023-05-01T10:00:01Z not json\n"
+        );
+
+        let out = String::from_utf8(display.format(
+            br#"{"time":"10:00","level":"warn","message":"slow"}"#,
+            false,
+            false,
+        ))
+        .unwrap();
+
+        assert_eq!(out, format!("{:30} WARN  slow", "10:00"));
+    }
+
+    #[test]
+    fn test_logs_display_from_logs_args() {
+        let args = LogsArgs {
+            json_field: vec!["level=error".to_string()],
+            grep: Some("time(out)?".to_string()),
+            pretty: true,
+            ..Default::default()
+        };
+
+        let display = LogsDisplay::try_from(&args).unwrap();
+
+        assert_eq!(
+            display.json_fields,
+            vec![("level".to_string(), "error".to_string())]
+        );
+        assert!(display.grep.unwrap().is_match("timeout"));
+        assert!(display.pretty);
+
+        let args = LogsArgs {
+            grep: Some("(".to_string()),
+            ..Default::default()
+        };
+
+        assert!(LogsDisplay::try_from(&args).is_err());
+
+        let args = LogsArgs {
+            json_field: vec!["level".to_string()],
+            ..Default::default()
+        };
+
+        assert!(LogsDisplay::try_from(&args).is_err());
+    }
+
+    #[test]
+    fn test_format_event() {
+        let event = EventMessage {
+            typ: Some(EventMessageTypeEnum::CONTAINER),
+            action: Some("start".to_string()),
+            actor: Some(EventActor {
+                id: Some("abc".to_string()),
+                attributes: Some(HashMap::from([
+                    ("name".to_string(), "web".to_string()),
+                    ("image".to_string(), "nginx".to_string()),
+                ])),
+            }),
+            time: Some(1682935200),
+            time_nano: Some(1682935200123456789),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            event_line(&event, &Utc),
+            "2023-05-01T10:00:00.123456789Z container start abc (image=nginx, name=web)"
+        );
+
+        let event = EventMessage {
+            time_nano: None,
+            ..event
+        };
+
+        assert!(event_line(&event, &Utc).starts_with("2023-05-01T10:00:00.000000000Z"));
+    }
+
+    #[test]
+    fn test_format_event_template() {
+        let event = EventMessage {
+            typ: Some(EventMessageTypeEnum::CONTAINER),
+            action: Some("stop".to_string()),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            format_event(&event, Some("{{.Type}} {{.Action}}")).unwrap(),
+            "container stop"
+        );
+        assert!(format_event(&event, Some("json"))
+            .unwrap()
+            .contains(r#""Action":"stop""#));
+    }
+}
@@ -0,0 +1,284 @@
+use std::{fmt::Display, str::FromStr};
+
+use crate::error::{ensure, Error, Result};
+
+/// Default registry used for images without a registry.
+pub(crate) const DEFAULT_REGISTRY: &str = "docker.io";
+const LEGACY_DEFAULT_REGISTRY: &str = "index.docker.io";
+const OFFICIAL_REPOSITORY: &str = "library";
+const DEFAULT_TAG: &str = "latest";
+
+/// A normalized image reference in the form `registry/path[:tag][@digest]`.
+///
+/// Images without a registry are normalized to `docker.io`, and single component paths on the
+/// default registry to `library/<name>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reference {
+    pub registry: String,
+    pub path: String,
+    pub tag: Option<String>,
+    pub digest: Option<String>,
+}
+
+impl Reference {
+    /// Adds the `latest` tag to a reference without a tag or digest.
+    pub fn with_default_tag(mut self) -> Self {
+        if self.tag.is_none() && self.digest.is_none() {
+            self.tag = Some(DEFAULT_TAG.to_string());
+        }
+
+        self
+    }
+
+    /// Returns the repository name, without tag and digest.
+    pub fn name(&self) -> String {
+        format!("{}/{}", self.registry, self.path)
+    }
+}
+
+impl FromStr for Reference {
+    type Err = Error;
+
+    fn from_str(input: &str) -> Result<Self> {
+        let (name, digest) = match input.split_once('@') {
+            Some((name, digest)) => {
+                ensure!(
+                    is_digest(digest),
+                    Parse,
+                    "Invalid digest {} in {}",
+                    digest,
+                    input
+                );
+
+                (name, Some(digest.to_string()))
+            }
+            None => (input, None),
+        };
+
+        // The tag can only be in the last component, since the registry can contain a port
+        let last_slash = name.rfind('/').map_or(0, |i| i + 1);
+
+        let (name, tag) = match name[last_slash..].rsplit_once(':') {
+            Some((_, tag)) => {
+                ensure!(is_tag(tag), Parse, "Invalid tag {} in {}", tag, input);
+
+                (&name[..name.len() - tag.len() - 1], Some(tag.to_string()))
+            }
+            None => (name, None),
+        };
+
+        let (registry, path) = match name.split_once('/') {
+            Some((registry, path)) if registry.contains(['.', ':']) || registry == "localhost" => {
+                (registry, path)
+            }
+            _ => (DEFAULT_REGISTRY, name),
+        };
+
+        let registry = if registry == LEGACY_DEFAULT_REGISTRY {
+            DEFAULT_REGISTRY
+        } else {
+            registry
+        };
+
+        ensure!(
+            !path.contains(|c: char| c.is_ascii_uppercase()),
+            Parse,
+            "Invalid reference format {}: repository name must be lowercase",
+            input
+        );
+        ensure!(
+            !path.is_empty() && path.split('/').all(is_path_component),
+            Parse,
+            "Invalid reference format {}: invalid repository name {}",
+            input,
+            path
+        );
+
+        let path = if registry == DEFAULT_REGISTRY && !path.contains('/') {
+            format!("{}/{}", OFFICIAL_REPOSITORY, path)
+        } else {
+            path.to_string()
+        };
+
+        Ok(Self {
+            registry: registry.to_string(),
+            path,
+            tag,
+            digest,
+        })
+    }
+}
+
+impl Display for Reference {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.registry, self.path)?;
+
+        if let Some(tag) = &self.tag {
+            write!(f, ":{}", tag)?;
+        }
+
+        if let Some(digest) = &self.digest {
+            write!(f, "@{}", digest)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Checks if the input is an image id or an id prefix (e.g. `sha256:4e7f...` or `4e7f...`).
+pub fn is_image_id(input: &str) -> bool {
+    let id = input.strip_prefix("sha256:").unwrap_or(input);
+
+    !id.is_empty() && id.len() <= 64 && id.chars().all(|c| matches!(c, '0'..='9' | 'a'..='f'))
+}
+
+fn is_digest(input: &str) -> bool {
+    match input.split_once(':') {
+        Some(("sha256", hex)) => hex.len() == 64 && hex.chars().all(|c| c.is_ascii_hexdigit()),
+        Some((algorithm, hex)) => {
+            !algorithm.is_empty()
+                && hex.len() >= 32
+                && algorithm
+                    .chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || "+._-".contains(c))
+                && hex.chars().all(|c| c.is_ascii_hexdigit())
+        }
+        None => false,
+    }
+}
+
+fn is_tag(input: &str) -> bool {
+    let mut chars = input.chars();
+
+    input.len() <= 128
+        && chars
+            .next()
+            .is_some_and(|c| c.is_ascii_alphanumeric() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-'))
+}
+
+fn is_path_component(input: &str) -> bool {
+    let is_alphanumeric = |c: char| c.is_ascii_lowercase() || c.is_ascii_digit();
+
+    input.starts_with(is_alphanumeric)
+        && input.ends_with(is_alphanumeric)
+        && input
+            .chars()
+            .all(|c| is_alphanumeric(c) || matches!(c, '.' | '_' | '-'))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    const DIGEST: &str = "sha256:4e7f8e1ba5d4c1cd0c2e7e8b9d0f32b6f2f7bb5f9e7b4d1ce8b5b0d8e1f20a3c";
+
+    fn reference(registry: &str, path: &str, tag: Option<&str>, digest: Option<&str>) -> Reference {
+        Reference {
+            registry: registry.to_string(),
+            path: path.to_string(),
+            tag: tag.map(str::to_string),
+            digest: digest.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_parse_reference() {
+        let with_digest = format!("repo@{}", DIGEST);
+        let with_tag_and_digest = format!("quay.io/org/app:v1@{}", DIGEST);
+
+        let cases = [
+            ("nginx", reference("docker.io", "library/nginx", None, None)),
+            (
+                "nginx:1.25",
+                reference("docker.io", "library/nginx", Some("1.25"), None),
+            ),
+            ("user/app", reference("docker.io", "user/app", None, None)),
+            (
+                "index.docker.io/user/app",
+                reference("docker.io", "user/app", None, None),
+            ),
+            (
+                with_digest.as_str(),
+                reference("docker.io", "library/repo", None, Some(DIGEST)),
+            ),
+            (
+                "localhost:5000/app",
+                reference("localhost:5000", "app", None, None),
+            ),
+            (
+                "localhost/app:v2",
+                reference("localhost", "app", Some("v2"), None),
+            ),
+            (
+                "registry.example.com/team/sub/app:1.0-rc.1",
+                reference(
+                    "registry.example.com",
+                    "team/sub/app",
+                    Some("1.0-rc.1"),
+                    None,
+                ),
+            ),
+            (
+                with_tag_and_digest.as_str(),
+                reference("quay.io", "org/app", Some("v1"), Some(DIGEST)),
+            ),
+        ];
+
+        for (input, expected) in cases {
+            assert_eq!(input.parse::<Reference>().unwrap(), expected, "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_parse_reference_invalid() {
+        let cases = [
+            "",
+            "Nginx",
+            "nginx:",
+            "nginx:-tag",
+            "repo@sha256:abc",
+            "repo@",
+            "localhost:5000/",
+            "user//app",
+            "user/app_",
+            ":tag",
+        ];
+
+        for input in cases {
+            assert!(input.parse::<Reference>().is_err(), "{}", input);
+        }
+
+        let message = |input: &str| input.parse::<Reference>().unwrap_err().to_string();
+
+        assert!(message("Nginx").ends_with("repository name must be lowercase"));
+        assert!(message("user//app").ends_with("invalid repository name user//app"));
+        assert!(message("user/app-").ends_with("invalid repository name user/app-"));
+    }
+
+    #[test]
+    fn test_reference_display() {
+        let reference: Reference = "localhost:5000/app".parse().unwrap();
+
+        assert_eq!(
+            reference.with_default_tag().to_string(),
+            "localhost:5000/app:latest"
+        );
+
+        let reference: Reference = format!("nginx@{}", DIGEST).parse().unwrap();
+
+        assert_eq!(
+            reference.with_default_tag().to_string(),
+            format!("docker.io/library/nginx@{}", DIGEST)
+        );
+    }
+
+    #[test]
+    fn test_is_image_id() {
+        assert!(is_image_id("4e7f8e1ba5d4"));
+        assert!(is_image_id(DIGEST));
+        assert!(!is_image_id("nginx"));
+        assert!(!is_image_id(""));
+    }
+}
@@ -1,252 +1,116 @@
-use std::{collections::HashMap, io::stdout, ops::DerefMut, sync::Arc};
+use std::{collections::HashSet, time::Duration};
 
-use bollard::{
-    container::{ListContainersOptions, Stats, StatsOptions},
-    service::ContainerSummary,
+use bollard::container::{ListContainersOptions, Stats, StatsOptions};
+use futures::{
+    stream::{self, BoxStream, SelectAll},
+    Stream, StreamExt,
 };
-use color_eyre::{
-    eyre::{Context, ContextCompat},
-    Result,
-};
-use crossterm::{
-    cursor::MoveToRow,
-    execute,
-    terminal::{Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen},
-};
-use futures::StreamExt;
-use tokio::{signal::ctrl_c, sync::Mutex, task::JoinHandle};
-use tracing::{debug, error, info, instrument, trace};
+use tracing::{debug, info, instrument, trace, warn};
 
+use crate::error::Result;
 #[cfg(feature = "mock")]
 use crate::mock::{DockerTrait, MockDocker as Docker};
 #[cfg(not(feature = "mock"))]
 use bollard::Docker;
 
-struct Containers {
-    stats: HashMap<String, Arc<Mutex<Option<Stats>>>>,
-    joins: Vec<JoinHandle<Result<()>>>,
-}
-
-impl Containers {
-    fn new() -> Self {
-        Self {
-            stats: HashMap::new(),
-            joins: Vec::new(),
-        }
-    }
-
-    async fn update(
-        &mut self,
-        docker: &Docker,
-        options: StatsOptions,
-        containers: &[ContainerSummary],
-    ) -> Result<()> {
-        for container in containers {
-            let id = container.id.as_deref().wrap_err("Conainer without id")?;
-
-            if self.stats.contains_key(id) {
-                continue;
-            }
-
-            let stat: Arc<Mutex<Option<Stats>>> = Arc::new(Mutex::new(None));
-
-            self.stats.insert(id.to_string(), stat.clone());
-
-            let dc_clone = docker.clone();
-            let id_c = id.to_string();
-
-            let join = tokio::spawn(async move {
-                let res = recv_stats(&dc_clone, &id_c, options, stat).await;
-
-                if let Err(ref e) = res {
-                    error!(?e, "Error while receiving stats");
-                }
-
-                res
-            });
-
-            self.joins.push(join);
-        }
+/// How often the containers are listed to get the stats of the new ones.
+const LIST_INTERVAL: Duration = Duration::from_secs(1);
 
-        Ok(())
-    }
+/// An update of the stats of the containers.
+#[derive(Debug, Clone, PartialEq)]
+pub enum StatsEvent {
+    /// The last stats of a container
+    Update(Box<Stats>),
+    /// The stats of the container with this id ended, it stopped or was removed
+    Ended(String),
 }
 
-#[instrument(skip(docker))]
-async fn recv_stats(
-    docker: &Docker,
-    id: &str,
-    options: StatsOptions,
-    stat: Arc<Mutex<Option<Stats>>>,
-) -> Result<()> {
-    let mut stream = docker.stats(id, Some(options));
-
-    debug!("Waiting for first stats item");
-
-    while let Some(item) = stream.next().await {
-        trace!(?item);
-
-        stat.lock().await.replace(item?);
-    }
-
-    stat.lock().await.take();
+/// A stats stream of a container, ending with `None` once the container stats stream is closed.
+type ContainerStream = BoxStream<'static, (String, Option<Result<Stats, bollard::errors::Error>>)>;
 
-    Ok(())
+/// The stats streams of the containers found so far.
+struct Containers<'a> {
+    docker: &'a Docker,
+    streams: SelectAll<ContainerStream>,
+    ids: HashSet<String>,
+    interval: tokio::time::Interval,
 }
 
-#[instrument]
-pub async fn stats(docker: &Docker, keep_screen: bool) -> Result<()> {
-    debug!("Intializing stats");
-
-    let stats = initialize_stats(docker).await?;
-
-    debug!("Starting stats loop");
-
-    let mut interval = tokio::time::interval(std::time::Duration::from_millis(100));
-
-    if !keep_screen {
-        execute!(stdout(), EnterAlternateScreen)?;
+impl Containers<'_> {
+    /// Attach to the stats of the containers that weren't found before.
+    async fn update(&mut self) -> Result<()> {
+        let list_options = ListContainersOptions::<&str> {
+            all: true,
+            ..Default::default()
+        };
 
-        tokio::spawn(async move {
-            ctrl_c().await.unwrap();
-            execute!(stdout(), LeaveAlternateScreen).unwrap();
-            std::process::exit(0);
-        });
-    }
-
-    loop {
-        if !keep_screen {
-            execute!(stdout(), Clear(ClearType::All), MoveToRow(0))?;
-        }
+        let stats_options = StatsOptions {
+            stream: true,
+            ..Default::default()
+        };
 
-        println!("Container ID\tName\tCPU\tMemory\tNetwork\tBlock I/O");
+        let containers = self.docker.list_containers(Some(list_options)).await?;
 
-        for stat in stats.lock().await.stats.values() {
-            let stat_guard = stat.lock().await;
+        info!("Found {} containers", containers.len());
 
-            let stat = if let Some(stat) = stat_guard.as_ref() {
-                stat
-            } else {
+        for id in containers.into_iter().filter_map(|container| container.id) {
+            if !self.ids.insert(id.clone()) {
                 continue;
-            };
-
-            let id = stat.id.as_str();
-            let name = stat.name.as_str();
-
-            let cpu = stat.cpu_stats.cpu_usage.total_usage;
-
-            let memory = stat
-                .memory_stats
-                .usage
-                .map(|s| s.to_string())
-                .unwrap_or_else(|| "-".to_string());
+            }
 
-            let net = stat
-                .network
-                .map(|s| s.rx_bytes.to_string())
-                .unwrap_or_else(|| "-".to_string());
+            debug!(id, "Waiting for first stats item");
 
-            let read = stat
-                .storage_stats
-                .read_size_bytes
-                .map_or_else(|| "-".to_string(), |s| s.to_string());
-            let write = stat
-                .storage_stats
-                .write_size_bytes
-                .map_or_else(|| "-".to_string(), |s| s.to_string());
+            let stream = self
+                .docker
+                .stats(&id, Some(stats_options))
+                .map(Some)
+                .chain(stream::once(async { None }))
+                .map(move |item| (id.clone(), item));
 
-            println!(
-                "{}\t{}\t{}\t{}\t{}\t{}/{}",
-                id, name, cpu, memory, net, read, write
-            );
+            self.streams.push(stream.boxed());
         }
 
-        check_failed(stats.lock().await.deref_mut()).await?;
-
-        interval.tick().await;
-    }
-}
-
-/// Check if any of the threads has failed
-async fn check_failed(stats: &mut Containers) -> Result<()> {
-    let (finished, running) = stats.joins.drain(..).fold(
-        (Vec::new(), Vec::new()),
-        |(mut finished, mut running), join| {
-            if join.is_finished() {
-                finished.push(join);
-            } else {
-                running.push(join);
-            }
-
-            (finished, running)
-        },
-    );
-
-    for join in finished {
-        join.await
-            .wrap_err("Erro joining thread")?
-            .wrap_err("Error in task")?;
+        Ok(())
     }
-
-    stats.joins.extend(running);
-
-    Ok(())
 }
 
+/// Streams the stats of all the containers, including the ones created afterwards.
+///
+/// The containers are listed every second, a container whose stats can't be received is logged
+/// and skipped, and the end of the stats of a container is reported with [`StatsEvent::Ended`].
 #[instrument]
-async fn initialize_stats(docker: &Docker) -> Result<Arc<Mutex<Containers>>> {
-    let stats = Arc::new(Mutex::new(Containers::new()));
-
-    let list_options = ListContainersOptions::<&str> {
-        all: true,
-        ..Default::default()
-    };
-
-    let stats_options = StatsOptions {
-        stream: true,
-        ..Default::default()
+pub fn stats(docker: &Docker) -> impl Stream<Item = Result<StatsEvent>> + '_ {
+    let containers = Containers {
+        docker,
+        streams: SelectAll::new(),
+        ids: HashSet::new(),
+        interval: tokio::time::interval(LIST_INTERVAL),
     };
 
-    let mut interval = tokio::time::interval(std::time::Duration::from_secs(1));
-
-    let stats_clone = stats.clone();
-
-    let docker = docker.clone();
-    let join: JoinHandle<Result<()>> = tokio::spawn(async move {
-        let span = tracing::trace_span!("update_containers");
-        let _enter = span.enter();
-
+    stream::unfold(containers, |mut containers| async move {
         loop {
-            let containers = match docker.list_containers(Some(list_options.clone())).await {
-                Ok(containers) => containers,
-                Err(e) => {
-                    error!(?e, "Failed to list containers");
-
-                    return Err(e.into());
+            tokio::select! {
+                Some((id, item)) = containers.streams.next(), if !containers.streams.is_empty() => {
+                    trace!(?item);
+
+                    match item {
+                        Some(Ok(stats)) => return Some((Ok(StatsEvent::Update(Box::new(stats))), containers)),
+                        Some(Err(error)) => warn!(id, %error, "Error while receiving stats"),
+                        None => {
+                            debug!(id, "Container stats ended");
+
+                            return Some((Ok(StatsEvent::Ended(id)), containers));
+                        }
+                    }
+                }
+                _ = containers.interval.tick() => {
+                    if let Err(error) = containers.update().await {
+                        return Some((Err(error), containers));
+                    }
                 }
-            };
-
-            info!("Found {} containers", containers.len());
-
-            let res = stats_clone
-                .lock()
-                .await
-                .update(&docker, stats_options, &containers)
-                .await;
-
-            if let Err(e) = res {
-                error!(?e, "Failed to update containers");
-
-                return Err(e);
             }
-
-            interval.tick().await;
         }
-    });
-
-    stats.lock().await.joins.push(join);
-
-    Ok(stats)
+    })
 }
 
 #[cfg(test)]
@@ -258,49 +122,37 @@ mod test {
     #[tokio::test]
     async fn test_stats() {
         let docker = docker_test!({
-            let mut mock = Docker::new();
-
-            mock.expect_clone().returning(|| {
-                let mut docker = Docker::new();
-
-                docker.expect_list_containers().returning(|_| {
-                    Ok(vec![ContainerSummary {
-                        id: Some("id".to_string()),
-                        ..Default::default()
-                    }])
-                });
+            use bollard::service::ContainerSummary;
 
-                docker.expect_clone().returning(|| {
-                    let mut docker = Docker::new();
-                    docker
-                        .expect_stats()
-                        .returning(|_, _| futures::stream::empty().boxed());
-
-                    docker
-                });
+            let mut mock = Docker::new();
 
-                docker
+            mock.expect_list_containers().returning(|_| {
+                Ok(vec![ContainerSummary {
+                    id: Some("id".to_string()),
+                    ..Default::default()
+                }])
             });
+            // The container is attached once, even if it's listed again
+            mock.expect_stats()
+                .times(1)
+                .returning(|_, _| futures::stream::empty().boxed());
 
             mock
         });
 
-        let join = tokio::spawn(async move {
-            let res = stats(&docker, true).await;
+        let stats = stats(&docker);
 
-            assert!(res.is_ok());
-        });
+        futures::pin_mut!(stats);
 
-        tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+        if cfg!(feature = "mock") {
+            let ended = stats.next().await.unwrap().unwrap();
 
-        join.abort();
+            assert_eq!(ended, StatsEvent::Ended("id".to_string()));
+        }
 
-        let err = join.await.unwrap_err();
+        // The stream goes on while there are containers to wait for
+        let result = tokio::time::timeout(Duration::from_millis(1500), stats.next()).await;
 
-        assert!(
-            err.is_cancelled(),
-            "Thread panicked or error occurred {:?}",
-            err
-        );
+        assert!(result.is_err(), "stats ended with {:?}", result);
     }
 }
@@ -0,0 +1,130 @@
+use serde_json::Value;
+
+use crate::error::{Error, Result};
+
+/// Renders a template in the format used by the docker CLI (e.g. `{{.Actor.Attributes.name}}`).
+///
+/// The fields are looked up in the JSON value, with a case insensitive fallback (e.g. `.Time`
+/// for the `time` field). `{{json .Field}}` renders the field as JSON, and missing fields are
+/// rendered as `<no value>`.
+pub fn render(template: &str, value: &Value) -> Result<String> {
+    render_with(template, value, str::to_string)
+}
+
+/// Renders a template for a shell command, quoting each rendered field.
+///
+/// The fields are quoted even inside quotes of the template, which would then be part of the
+/// value.
+pub(crate) fn render_shell(template: &str, value: &Value) -> Result<String> {
+    render_with(template, value, |field| {
+        format!("'{}'", field.replace('\'', r"'\''"))
+    })
+}
+
+fn render_with(template: &str, value: &Value, escape: impl Fn(&str) -> String) -> Result<String> {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(start) = rest.find("{{") {
+        out.push_str(&rest[..start]);
+
+        let end = rest[start..]
+            .find("}}")
+            .ok_or_else(|| Error::Parse(format!("Unclosed action in template {}", template)))?;
+
+        let action = rest[start + 2..start + end].trim();
+
+        let (json, path) = match action.strip_prefix("json ") {
+            Some(path) => (true, path.trim()),
+            None => (false, action),
+        };
+
+        let field = lookup(value, path)?;
+
+        let field = match field {
+            Some(field) if json => field.to_string(),
+            Some(Value::String(field)) => field.clone(),
+            Some(Value::Null) | None => "<no value>".to_string(),
+            Some(field) => field.to_string(),
+        };
+
+        out.push_str(&escape(&field));
+
+        rest = &rest[start + end + 2..];
+    }
+
+    out.push_str(rest);
+
+    Ok(out)
+}
+
+/// Looks up a path (e.g. `.Actor.ID`) in a JSON value, `.` being the value itself.
+fn lookup<'a>(value: &'a Value, path: &str) -> Result<Option<&'a Value>> {
+    let Some(path) = path.strip_prefix('.') else {
+        return Err(Error::Parse(format!("Invalid template field {}", path)));
+    };
+
+    if path.is_empty() {
+        return Ok(Some(value));
+    }
+
+    let field = path.split('.').try_fold(value, |value, key| {
+        let object = value.as_object()?;
+
+        object.get(key).or_else(|| {
+            object
+                .iter()
+                .find(|(name, _)| name.eq_ignore_ascii_case(key))
+                .map(|(_, value)| value)
+        })
+    });
+
+    Ok(field)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    #[test]
+    fn test_render() {
+        let value = json!({
+            "Type": "container",
+            "Actor": {"ID": "abc", "Attributes": {"name": "web"}},
+            "time": 1682935200,
+        });
+
+        let cases = [
+            ("{{.Type}} {{.Actor.Attributes.name}}", "container web"),
+            ("{{ .Time }}", "1682935200"),
+            ("{{.Actor.Attributes.image}}", "<no value>"),
+            ("{{json .Actor.Attributes}}", r#"{"name":"web"}"#),
+            ("no actions", "no actions"),
+        ];
+
+        for (template, expected) in cases {
+            assert_eq!(render(template, &value).unwrap(), expected, "{}", template);
+        }
+
+        assert!(render("{{.Type", &value).is_err());
+        assert!(render("{{Type}}", &value).is_err());
+    }
+
+    #[test]
+    fn test_render_shell() {
+        let value = json!({"Actor": {"Attributes": {"name": "it's $(web)"}}});
+
+        assert_eq!(
+            render_shell("notify-send {{.Actor.Attributes.name}}", &value).unwrap(),
+            r"notify-send 'it'\''s $(web)'"
+        );
+
+        // The quotes of the template don't disable the quoting of the fields
+        assert_eq!(
+            render_shell("echo \"{{.Actor.Attributes.name}}\"", &value).unwrap(),
+            r#"echo "'it'\''s $(web)'""#
+        );
+    }
+}